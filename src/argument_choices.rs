@@ -0,0 +1,421 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::command_resolver::{resolve_command_template, resolve_default_values};
+use crate::command_runner::capture_command_output;
+use crate::error::ZxcError;
+use crate::zxc_command_defs::{Choices, ZxcCommandDef};
+
+/// Path of a file caching output of a command run in specified directory.
+/// Hash is not stable between builds - cache is only invalidated in such case.
+fn cache_file_path(cache_dir: &Path, command: &str, working_directory: &Path) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    command.hash(&mut hasher);
+    working_directory.hash(&mut hasher);
+    cache_dir.join(format!("choices-{:016x}", hasher.finish()))
+}
+
+/// Read cached output, if it's not older than specified duration.
+fn read_cache(cache_file_path: &Path, max_age: Duration) -> Option<String> {
+    let modified = fs::metadata(cache_file_path).ok()?.modified().ok()?;
+    let age = SystemTime::now().duration_since(modified).ok()?;
+    if age > max_age {
+        return None;
+    }
+    fs::read_to_string(cache_file_path).ok()
+}
+
+/// Compute allowed values of an argument.
+/// Non-empty lines of shell command output are used.
+/// Output is cached in specified directory, if caching is enabled and directory is known.
+pub fn compute_choices(
+    zxc_command_def: &ZxcCommandDef,
+    choices: &Choices,
+    arguments: &BTreeMap<String, String>,
+    shell: &str,
+    working_directory: &Path,
+    env: &BTreeMap<String, String>,
+    cache_dir: Option<&Path>,
+) -> Result<Vec<String>, ZxcError> {
+    let (template, cache) = match choices {
+        Choices::Values(values) => return Ok(values.clone()),
+        Choices::Shell { command, cache } => (command, cache),
+    };
+
+    // Resolve shell command.
+    let command_name = &zxc_command_def.name;
+    let command = match resolve_command_template(zxc_command_def, template, arguments) {
+        Ok(x) => x,
+        Err(e) => return Err(ZxcError::from_template_error(command_name, template, &e)),
+    };
+
+    // Use cached output, if available.
+    let cache_file = match (cache, cache_dir) {
+        (Some(max_age), Some(cache_dir)) => Some((
+            cache_file_path(cache_dir, &command, working_directory),
+            *max_age,
+        )),
+        _ => None,
+    };
+    let cached_output = match &cache_file {
+        Some((path, max_age)) => read_cache(path, *max_age),
+        None => None,
+    };
+
+    let output = match cached_output {
+        Some(x) => x,
+        None => {
+            let output = match capture_command_output(&command, shell, working_directory, env) {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(ZxcError::Execution {
+                        command: command_name.to_string(),
+                        source: e,
+                    });
+                }
+            };
+            // Failure to cache output is not critical.
+            if let (Some((path, _)), Some(cache_dir)) = (&cache_file, cache_dir) {
+                let _ = fs::create_dir_all(cache_dir).and_then(|_| fs::write(path, &output));
+            }
+            output
+        }
+    };
+
+    Ok(output
+        .lines()
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Resolve values of arguments which are not provided and check all values against choices.
+/// Default values are resolved first, so they're checked as well.
+/// Value of argument with choices and without default value is selected using provided function
+/// if not provided. Arguments used in relations are optional - their values are not selected.
+pub fn resolve_choices<F>(
+    zxc_command_def: &ZxcCommandDef,
+    arguments: &mut BTreeMap<String, String>,
+    shell: &str,
+    working_directory: &Path,
+    env: &BTreeMap<String, String>,
+    cache_dir: Option<&Path>,
+    mut select: F,
+) -> Result<(), ZxcError>
+where
+    F: FnMut(&str, &[String]) -> Result<Option<String>, ZxcError>,
+{
+    let usage_error = |message: String| ZxcError::Usage {
+        command: zxc_command_def.name.clone(),
+        message,
+    };
+    let compute = |choices: &Choices, arguments: &BTreeMap<String, String>| {
+        compute_choices(
+            zxc_command_def,
+            choices,
+            arguments,
+            shell,
+            working_directory,
+            env,
+            cache_dir,
+        )
+        .map_err(|e| e.with_origin(zxc_command_def.origin.as_ref()))
+    };
+
+    // Resolve default values and select missing values.
+    // Selected values are valid, they're not checked again.
+    let mut selected = BTreeSet::new();
+    resolve_default_values(
+        zxc_command_def,
+        arguments,
+        shell,
+        working_directory,
+        env,
+        |zxc_argument_def, arguments| {
+            let name = &zxc_argument_def.name;
+            let values = match &zxc_argument_def.choices {
+                Some(x) => compute(x, arguments)?,
+                None => vec![],
+            };
+            match select(name, &values)? {
+                Some(x) if values.contains(&x) => {
+                    selected.insert(name.clone());
+                    Ok(x)
+                }
+                _ => Err(usage_error(format!(
+                    "argument '{name}' is required, possible values: {}",
+                    values.join(", ")
+                ))),
+            }
+        },
+    )?;
+
+    // Check values, including default ones.
+    for zxc_argument_def in &zxc_command_def.arguments {
+        let name = &zxc_argument_def.name;
+        let (choices, value) = match (&zxc_argument_def.choices, arguments.get(name)) {
+            (Some(x), Some(y)) if !selected.contains(name) => (x, y),
+            _ => continue,
+        };
+
+        let values = compute(choices, arguments)?;
+        if !values.contains(value) {
+            return Err(usage_error(format!(
+                "invalid value '{value}' for argument '{name}', possible values: {}",
+                values.join(", ")
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod compute_choices_tests {
+    use super::compute_choices;
+    use crate::settings::default_shell;
+    use crate::zxc_command_defs::{Choices, ZxcCommandDef};
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn command_def() -> ZxcCommandDef {
+        ZxcCommandDef {
+            name: "logs".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn shell_command() {
+        let temp_dir = tempdir().unwrap();
+        let choices = Choices::Shell {
+            command: "printf 'api\\n\\n{{ prefix }}-worker\\n'".to_string(),
+            cache: None,
+        };
+        let mut arguments = BTreeMap::new();
+        arguments.insert("prefix".to_string(), "app".to_string());
+
+        let result = compute_choices(
+            &command_def(),
+            &choices,
+            &arguments,
+            &default_shell(),
+            temp_dir.path(),
+            &BTreeMap::new(),
+            Some(&temp_dir.path().join("cache")),
+        );
+        assert_eq!(result.unwrap(), vec!["api", "app-worker"]);
+        // Output is not cached.
+        assert!(!temp_dir.path().join("cache").exists());
+    }
+
+    #[test]
+    fn cached_output() {
+        let temp_dir = tempdir().unwrap();
+        let counter = temp_dir.path().join("counter");
+        let choices = Choices::Shell {
+            command: format!("echo x >> {}; echo api", counter.display()),
+            cache: Some(Duration::from_secs(60)),
+        };
+
+        for _ in 0..2 {
+            let result = compute_choices(
+                &command_def(),
+                &choices,
+                &BTreeMap::new(),
+                &default_shell(),
+                temp_dir.path(),
+                &BTreeMap::new(),
+                Some(&temp_dir.path().join("cache")),
+            );
+            assert_eq!(result.unwrap(), vec!["api"]);
+        }
+        // Command is run only once.
+        assert_eq!(fs::read_to_string(counter).unwrap().lines().count(), 1);
+    }
+
+    #[test]
+    fn unknown_cache_dir() {
+        let temp_dir = tempdir().unwrap();
+        let counter = temp_dir.path().join("counter");
+        let choices = Choices::Shell {
+            command: format!("echo x >> {}; echo api", counter.display()),
+            cache: Some(Duration::from_secs(60)),
+        };
+
+        for _ in 0..2 {
+            let result = compute_choices(
+                &command_def(),
+                &choices,
+                &BTreeMap::new(),
+                &default_shell(),
+                temp_dir.path(),
+                &BTreeMap::new(),
+                None,
+            );
+            assert_eq!(result.unwrap(), vec!["api"]);
+        }
+        // Output is not cached.
+        assert_eq!(fs::read_to_string(counter).unwrap().lines().count(), 2);
+    }
+}
+
+#[cfg(test)]
+mod resolve_choices_tests {
+    use super::resolve_choices;
+    use crate::error::ZxcError;
+    use crate::settings::default_shell;
+    use crate::zxc_command_defs::{
+        ArgumentType, Choices, DefaultValue, FlagType, ZxcArgumentDef, ZxcCommandDef,
+    };
+    use std::collections::BTreeMap;
+    use tempfile::tempdir;
+
+    fn command_def(default: Option<DefaultValue>) -> ZxcCommandDef {
+        ZxcCommandDef {
+            name: "logs".to_string(),
+            command: "docker compose logs {{ service }}".to_string(),
+            arguments: vec![ZxcArgumentDef {
+                name: "service".to_string(),
+                flags: vec![ArgumentType::Positional],
+                default,
+                choices: Some(Choices::Shell {
+                    command: "printf 'api\\nworker\\n'".to_string(),
+                    cache: None,
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn resolve(
+        zxc_command_def: &ZxcCommandDef,
+        arguments: &mut BTreeMap<String, String>,
+        selected: Option<&str>,
+    ) -> Result<(), ZxcError> {
+        let temp_dir = tempdir().unwrap();
+        resolve_choices(
+            zxc_command_def,
+            arguments,
+            &default_shell(),
+            temp_dir.path(),
+            &BTreeMap::new(),
+            Some(temp_dir.path()),
+            |_, _| Ok(selected.map(str::to_string)),
+        )
+    }
+
+    #[test]
+    fn valid_value() {
+        let mut arguments = BTreeMap::new();
+        arguments.insert("service".to_string(), "worker".to_string());
+
+        let result = resolve(&command_def(None), &mut arguments, None);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn invalid_value() {
+        let mut arguments = BTreeMap::new();
+        arguments.insert("service".to_string(), "db".to_string());
+
+        let result = resolve(&command_def(None), &mut arguments, None);
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Usage { .. })
+            && e.to_string().contains("possible values: api, worker")));
+    }
+
+    #[test]
+    fn selected_value() {
+        let mut arguments = BTreeMap::new();
+
+        let result = resolve(&command_def(None), &mut arguments, Some("api"));
+        assert!(result.is_ok());
+        assert_eq!(arguments.get("service").unwrap(), "api");
+    }
+
+    #[test]
+    fn missing_value() {
+        let mut arguments = BTreeMap::new();
+
+        let result = resolve(&command_def(None), &mut arguments, None);
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Usage { .. })));
+    }
+
+    #[test]
+    fn default_value() {
+        let default = Some(DefaultValue::Shell("echo worker".to_string()));
+        let mut arguments = BTreeMap::new();
+
+        // Selection is not requested - default value is used.
+        let result = resolve(&command_def(default), &mut arguments, None);
+        assert!(result.is_ok());
+        assert_eq!(arguments.get("service").unwrap(), "worker");
+    }
+
+    #[test]
+    fn invalid_default_value() {
+        let default = Some(DefaultValue::Shell("echo db".to_string()));
+        let mut arguments = BTreeMap::new();
+
+        let result = resolve(&command_def(default), &mut arguments, None);
+        assert!(result.is_err_and(|e| e.to_string().contains("invalid value 'db'")));
+    }
+
+    #[test]
+    fn choices_referencing_default_value() {
+        let mut zxc_command_def = command_def(None);
+        zxc_command_def.arguments[0].choices = Some(Choices::Shell {
+            command: "echo {{ env }}-api".to_string(),
+            cache: None,
+        });
+        zxc_command_def.arguments.insert(
+            0,
+            ZxcArgumentDef {
+                name: "env".to_string(),
+                flags: vec![ArgumentType::Named(FlagType::Long("env".to_string()))],
+                default: Some(DefaultValue::Shell("echo prod".to_string())),
+                ..Default::default()
+            },
+        );
+
+        let mut arguments = BTreeMap::new();
+        arguments.insert("service".to_string(), "prod-api".to_string());
+        let result = resolve(&zxc_command_def, &mut arguments, None);
+        assert!(result.is_ok());
+        assert_eq!(arguments.get("env").unwrap(), "prod");
+
+        let mut arguments = BTreeMap::new();
+        let result = resolve(&zxc_command_def, &mut arguments, Some("prod-api"));
+        assert!(result.is_ok());
+        assert_eq!(arguments.get("service").unwrap(), "prod-api");
+    }
+
+    #[test]
+    fn related_argument() {
+        for choices in [
+            Choices::Values(vec!["api".to_string()]),
+            Choices::Shell {
+                command: "echo api".to_string(),
+                cache: None,
+            },
+        ] {
+            let mut zxc_command_def = command_def(None);
+            zxc_command_def.arguments[0].choices = Some(choices);
+            zxc_command_def.one_of = vec![vec!["service".to_string(), "config".to_string()]];
+            let mut arguments = BTreeMap::new();
+            arguments.insert("config".to_string(), "x".to_string());
+
+            // Selection is not requested - argument is optional.
+            let result = resolve(&zxc_command_def, &mut arguments, None);
+            assert!(result.is_ok());
+            assert!(!arguments.contains_key("service"));
+        }
+    }
+}
@@ -1,66 +1,899 @@
-use crate::zxc_command_defs::{ArgumentType, FlagType, ZxcCommandDef, ZxcCommandDefs};
-use clap::{Arg, Command, command};
+use crate::yaml_command_defs::{DefinitionScope, YamlExampleDef};
+use crate::zxc_command_defs::{
+    ArgumentType, Choices, DefaultValue, FlagType, ZxcCommandDef, ZxcCommandDefs, pattern_source,
+    relation_group_id,
+};
+use clap::builder::{PossibleValuesParser, StyledStr};
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Command, command};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+/// Global options.
+/// Those are available before definition files are loaded.
+#[derive(Debug, Default)]
+pub struct GlobalArgs {
+    /// Validate definition files instead of running a command.
+    pub check: bool,
+    /// Print JSON Schema of definition file instead of running a command.
+    pub print_schema: bool,
+    /// Print effective settings instead of running a command.
+    pub config_show: bool,
+    /// Import commands from a file of another tool instead of running a command.
+    pub import: Option<PathBuf>,
+    /// Explain where a command is defined instead of running it.
+    pub which: Option<String>,
+    /// Print additional information, overrides verbosity setting.
+    pub verbose: bool,
+    /// Explicit definition file, discovery is skipped.
+    pub file: Option<PathBuf>,
+    /// Directory used instead of CWD.
+    pub directory: Option<PathBuf>,
+    /// Print resolved command and sources of argument values instead of running it.
+    pub dry_run: bool,
+}
+
+impl GlobalArgs {
+    fn from_matches(matches: &ArgMatches) -> Self {
+        GlobalArgs {
+            check: matches.get_flag("check"),
+            print_schema: matches.get_flag("print_schema"),
+            config_show: matches.get_flag("config_show"),
+            import: matches.get_one::<PathBuf>("import").cloned(),
+            which: matches.get_one::<String>("which").cloned(),
+            verbose: matches.get_flag("verbose"),
+            file: matches.get_one::<PathBuf>("file").cloned(),
+            directory: matches.get_one::<PathBuf>("directory").cloned(),
+            dry_run: matches.get_flag("dry_run"),
+        }
+    }
+}
+
+/// Add global options to `Command` object.
+/// Options replacing running a command are mutually exclusive.
+fn add_global_args(cli_command: Command) -> Command {
+    cli_command
+        .group(ArgGroup::new("action").multiple(false))
+        .arg(
+            Arg::new("check")
+                .long("check")
+                .action(ArgAction::SetTrue)
+                .group("action")
+                .help("Validate definition files and report all problems found"),
+        )
+        .arg(
+            Arg::new("print_schema")
+                .long("print-schema")
+                .action(ArgAction::SetTrue)
+                .group("action")
+                .help("Print JSON Schema of definition file"),
+        )
+        .arg(
+            Arg::new("config_show")
+                .long("config-show")
+                .action(ArgAction::SetTrue)
+                .group("action")
+                .help("Print effective settings and their sources"),
+        )
+        .arg(
+            Arg::new("import")
+                .long("import")
+                .value_name("FILE")
+                .value_parser(clap::value_parser!(PathBuf))
+                .group("action")
+                .help("Print definition file translated from Makefile, justfile, package.json or Taskfile.yml"),
+        )
+        .arg(
+            Arg::new("which")
+                .long("which")
+                .visible_alias("explain")
+                .value_name("COMMAND")
+                .group("action")
+                .help("Print where a command is defined and which definitions it overrides"),
+        )
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .action(ArgAction::SetTrue)
+                .help("Print used definition files, overridden commands and run commands"),
+        )
+        .arg(
+            Arg::new("file")
+                .short('f')
+                .long("file")
+                .value_name("FILE")
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("Use specified definition file instead of discovering them"),
+        )
+        .arg(
+            Arg::new("directory")
+                .short('d')
+                .long("directory")
+                .value_name("DIR")
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("Discover definition files and run commands in specified directory"),
+        )
+        .arg(
+            Arg::new("dry_run")
+                .long("dry-run")
+                .action(ArgAction::SetTrue)
+                .help("Print resolved command and sources of argument values instead of running it"),
+        )
+}
+
+/// Parse global options from provided arguments.
+/// Subcommands and unknown options are ignored - those are handled by `build_cli`.
+pub fn parse_global_args<I, T>(args: I) -> GlobalArgs
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let cli_command = add_global_args(command!())
+        .disable_help_flag(true)
+        .disable_version_flag(true)
+        .allow_external_subcommands(true)
+        .ignore_errors(true);
+    match cli_command.try_get_matches_from(args) {
+        Ok(matches) => GlobalArgs::from_matches(&matches),
+        Err(_) => GlobalArgs::default(),
+    }
+}
+
+/// Describe resolved command and sources of argument values, e.g., for dry run.
+/// Precedence of sources is: command line, environment variable, default value.
+pub fn describe_resolved_command(
+    zxc_command_def: &ZxcCommandDef,
+    subcommand_args: &ArgMatches,
+    arguments: &BTreeMap<String, String>,
+    resolved_command: &str,
+) -> String {
+    let mut description = format!("command: {resolved_command}\n");
+    if zxc_command_def.arguments.is_empty() {
+        return description;
+    }
+
+    description.push_str("arguments:\n");
+    for zxc_argument_def in &zxc_command_def.arguments {
+        let name = &zxc_argument_def.name;
+        let value = arguments.get(name).cloned().unwrap_or_default();
+        // Dynamic default values are not known to CLI parser.
+        let source = match subcommand_args.value_source(name) {
+            Some(ValueSource::CommandLine) => "command line".to_string(),
+            Some(ValueSource::EnvVariable) => {
+                format!("${}", zxc_argument_def.env.as_deref().unwrap_or_default())
+            }
+            Some(ValueSource::DefaultValue) => "default".to_string(),
+            _ if arguments.contains_key(name) && zxc_argument_def.default.is_some() => {
+                "default".to_string()
+            }
+            _ if arguments.contains_key(name) => "selected".to_string(),
+            _ => "unknown".to_string(),
+        };
+        description.push_str(&format!("  {name}: {value} ({source})\n"));
+    }
+    description
+}
+
+/// Marker of deprecated commands in help.
+const DEPRECATED_MARKER: &str = "[deprecated] ";
 
 /// Build `Command` object for a subcommand.
 fn build_subcommand(zxc_command_def: &ZxcCommandDef) -> Command {
     let mut cli_command = Command::new(zxc_command_def.name.clone());
 
-    // Add 'about' section - containing description and command.
-    let mut about_string = String::new();
-    if let Some(description) = &zxc_command_def.description {
-        let formatted = format!("{description}\n\n");
-        about_string.push_str(&formatted);
+    // Add short 'about' section - shown in commands list and by `-h`.
+    if let Some(summary) = command_summary(zxc_command_def) {
+        cli_command = cli_command.about(summary);
     }
 
-    let formatted_shell_command = format!("Command: {}", &zxc_command_def.command);
-    about_string.push_str(&formatted_shell_command);
+    // Add long 'about' section - containing description, help, command and deprecation message.
+    let mut paragraphs = vec![];
+    if let Some(description) = &zxc_command_def.description {
+        paragraphs.push(description.clone());
+    }
+    if let Some(help) = &zxc_command_def.help {
+        paragraphs.push(help.trim_end().to_string());
+    }
+    if !zxc_command_def.hide_command {
+        paragraphs.push(format!("Command: {}", &zxc_command_def.command));
+    }
+    if let Some(deprecated) = &zxc_command_def.deprecated {
+        paragraphs.push(format!("Deprecated: {deprecated}"));
+    }
+    if let Some(first_paragraph) = paragraphs.first_mut()
+        && zxc_command_def.deprecated.is_some()
+    {
+        first_paragraph.insert_str(0, DEPRECATED_MARKER);
+    }
+    cli_command = cli_command.long_about(paragraphs.join("\n\n"));
 
-    cli_command = cli_command.about(about_string);
+    // Add examples.
+    if !zxc_command_def.examples.is_empty() {
+        let section = build_examples_section(&cli_command, &zxc_command_def.examples);
+        cli_command = cli_command.after_long_help(section);
+    }
 
     // Add arguments.
     for zxc_argument_def in &zxc_command_def.arguments {
-        let mut cli_argument = Arg::new(zxc_argument_def.name.clone());
+        let name = &zxc_argument_def.name;
+        let mut cli_argument = Arg::new(name.clone());
 
-        // Add flags.
+        // Add flags - first short and long flags are primary, others are aliases.
+        let mut has_short = false;
+        let mut has_long = false;
         for zxc_flag in &zxc_argument_def.flags {
             cli_argument = match zxc_flag {
                 ArgumentType::Named(flag_type) => match flag_type {
                     FlagType::Short(x) => {
                         let x = x.as_str().chars().next().unwrap();
-                        cli_argument.short(x)
+                        let is_alias = has_short;
+                        has_short = true;
+                        match is_alias {
+                            false => cli_argument.short(x),
+                            true => cli_argument.visible_short_alias(x),
+                        }
+                    }
+                    FlagType::Long(x) => {
+                        let is_alias = has_long;
+                        has_long = true;
+                        match is_alias {
+                            false => cli_argument.long(x),
+                            true => cli_argument.visible_alias(x),
+                        }
                     }
-                    FlagType::Long(x) => cli_argument.long(x),
                 },
                 // Nothing needs to be done for positional - argument name is used.
                 ArgumentType::Positional => cli_argument,
             }
         }
 
+        // Add hidden flags.
+        for zxc_flag in &zxc_argument_def.hidden_flags {
+            cli_argument = match zxc_flag {
+                FlagType::Short(x) => {
+                    let x = x.as_str().chars().next().unwrap();
+                    cli_argument.short_alias(x)
+                }
+                FlagType::Long(x) => cli_argument.alias(x),
+            }
+        }
+
+        // Add allowed values.
+        // Dynamic choices are computed after parsing, only for the command being run.
+        let mut help_suffixes = Vec::new();
+        match &zxc_argument_def.choices {
+            Some(Choices::Values(values)) => {
+                cli_argument = cli_argument.value_parser(PossibleValuesParser::new(values));
+            }
+            Some(Choices::Shell { command, .. }) => {
+                help_suffixes.push(format!("[possible values: $({command})]"));
+            }
+            None => (),
+        }
+
+        // Add value constraints.
+        if zxc_argument_def.is_constrained() {
+            let constrained_def = zxc_argument_def.clone();
+            cli_argument = cli_argument
+                .value_parser(move |x: &str| constrained_def.check_value(x).map(|_| x.to_string()));
+        }
+        if let Some(pattern) = &zxc_argument_def.pattern {
+            help_suffixes.push(format!("[pattern: {}]", pattern_source(pattern)));
+        }
+        if let Some(min) = zxc_argument_def.min {
+            help_suffixes.push(format!("[min: {min}]"));
+        }
+        if let Some(max) = zxc_argument_def.max {
+            help_suffixes.push(format!("[max: {max}]"));
+        }
+
+        // Add relations - related arguments are optional, relations are checked instead.
+        for required in zxc_command_def.requires.get(name).into_iter().flatten() {
+            cli_argument = cli_argument.requires(required);
+        }
+
         // Add default value.
-        cli_argument = match &zxc_argument_def.default {
-            Some(x) => cli_argument.default_value(x),
-            None => cli_argument.required(true),
+        // Dynamic default values are evaluated after parsing, only if argument is not provided.
+        // Arguments with choices are selected interactively if not provided.
+        match &zxc_argument_def.default {
+            Some(DefaultValue::Static(x)) => cli_argument = cli_argument.default_value(x),
+            Some(DefaultValue::Template(x)) => help_suffixes.push(format!("[default: {x}]")),
+            Some(DefaultValue::Shell(x)) => help_suffixes.push(format!("[default: $({x})]")),
+            None => (),
         };
+        if zxc_command_def.is_argument_required(zxc_argument_def) {
+            cli_argument = cli_argument.required(true);
+        }
 
         // Add description.
-        cli_argument = match &zxc_argument_def.description {
-            Some(x) => cli_argument.help(x),
+        let help: Vec<String> = zxc_argument_def
+            .description
+            .iter()
+            .cloned()
+            .chain(help_suffixes)
+            .collect();
+        if !help.is_empty() {
+            cli_argument = cli_argument.help(help.join(" "));
+        }
+
+        // Add environment variable - it takes precedence over default value.
+        cli_argument = match &zxc_argument_def.env {
+            Some(x) => cli_argument.env(x),
             None => cli_argument,
         };
 
         cli_command = cli_command.arg(cli_argument);
     }
 
+    // Add argument groups.
+    for (index, group) in zxc_command_def.conflicts.iter().enumerate() {
+        let cli_group = ArgGroup::new(relation_group_id("conflicts", index))
+            .args(group)
+            .multiple(false);
+        cli_command = cli_command.group(cli_group);
+    }
+    for (index, group) in zxc_command_def.one_of.iter().enumerate() {
+        let cli_group = ArgGroup::new(relation_group_id("one_of", index))
+            .args(group)
+            .multiple(false)
+            .required(true);
+        cli_command = cli_command.group(cli_group);
+    }
+
     cli_command
 }
 
+/// Summary of a command - first line of description or command.
+/// Deprecated commands are marked.
+fn command_summary(zxc_command_def: &ZxcCommandDef) -> Option<String> {
+    let first_line = |x: &str| x.lines().next().unwrap_or_default().to_string();
+    let summary = match &zxc_command_def.description {
+        Some(x) => first_line(x),
+        None if zxc_command_def.hide_command => return None,
+        None => format!("Command: {}", first_line(&zxc_command_def.command)),
+    };
+    match zxc_command_def.deprecated {
+        Some(_) => Some(format!("{DEPRECATED_MARKER}{summary}")),
+        None => Some(summary),
+    }
+}
+
+/// Build help section listing usage examples, styled same as `clap` sections.
+fn build_examples_section(cli_command: &Command, examples: &[YamlExampleDef]) -> StyledStr {
+    let styles = cli_command.get_styles();
+    let header = styles.get_header();
+    let literal = styles.get_literal();
+
+    let mut section = format!("{header}Examples:{header:#}\n");
+    for example in examples {
+        section.push_str(&format!("  {literal}{}{literal:#}\n", example.command));
+        if let Some(description) = &example.description {
+            for line in description.lines() {
+                section.push_str(&format!("      {line}\n"));
+            }
+        }
+    }
+    StyledStr::from(section.trim_end().to_string())
+}
+
+/// Build help section listing provided commands, styled same as `clap` sections.
+fn build_commands_section(
+    main_command: &Command,
+    title: &str,
+    zxc_command_defs: &[&ZxcCommandDef],
+) -> String {
+    let styles = main_command.get_styles();
+    let header = styles.get_header();
+    let literal = styles.get_literal();
+    let name_width = zxc_command_defs
+        .iter()
+        .map(|x| x.name.len())
+        .max()
+        .unwrap_or_default();
+
+    let mut section = format!("{header}{title}:{header:#}\n");
+    for zxc_command_def in zxc_command_defs {
+        // Summary is same as for other commands.
+        let summary = command_summary(zxc_command_def).unwrap_or_default();
+        let name = format!("{:name_width$}", zxc_command_def.name);
+        section.push_str(&format!("  {literal}{name}{literal:#}  {summary}\n"));
+    }
+    section.trim_end().to_string()
+}
+
 pub fn build_cli(zxc_command_defs: &ZxcCommandDefs) -> Command {
     // Create base `clap` command.
-    let mut main_command = command!().subcommand_required(true);
+    let mut main_command = add_global_args(command!()).subcommand_required(true);
     // Add subcommands.
+    // Hidden and private commands are not listed in help.
+    // Commands with a group and global commands are listed in separate help sections.
+    let mut grouped_command_defs: BTreeMap<&str, Vec<&ZxcCommandDef>> = BTreeMap::new();
+    let mut global_command_defs = vec![];
     for zxc_command_def in zxc_command_defs {
-        let subcommand = build_subcommand(zxc_command_def);
+        let mut subcommand = build_subcommand(zxc_command_def);
+        if zxc_command_def.hidden || zxc_command_def.private {
+            subcommand = subcommand.hide(true);
+        } else if let Some(group) = &zxc_command_def.group {
+            subcommand = subcommand.hide(true);
+            grouped_command_defs
+                .entry(group)
+                .or_default()
+                .push(zxc_command_def);
+        } else if zxc_command_def.scope == DefinitionScope::Global {
+            subcommand = subcommand.hide(true);
+            global_command_defs.push(zxc_command_def);
+        }
         main_command = main_command.subcommand(subcommand);
     }
+
+    let mut sections = vec![];
+    for (group, group_command_defs) in &grouped_command_defs {
+        sections.push(build_commands_section(
+            &main_command,
+            group,
+            group_command_defs,
+        ));
+    }
+    if !global_command_defs.is_empty() {
+        sections.push(build_commands_section(
+            &main_command,
+            "Global commands",
+            &global_command_defs,
+        ));
+    }
+    if !sections.is_empty() {
+        main_command = main_command.after_help(StyledStr::from(sections.join("\n\n")));
+    }
     main_command
 }
+
+#[cfg(test)]
+mod parse_global_args_tests {
+    use super::parse_global_args;
+    use std::path::PathBuf;
+
+    #[test]
+    fn check() {
+        let global_args = parse_global_args(["zxc", "--check"]);
+        assert!(global_args.check);
+    }
+
+    #[test]
+    fn print_schema() {
+        let global_args = parse_global_args(["zxc", "--print-schema"]);
+        assert!(global_args.print_schema);
+    }
+
+    #[test]
+    fn config_show() {
+        let global_args = parse_global_args(["zxc", "--config-show"]);
+        assert!(global_args.config_show);
+    }
+
+    #[test]
+    fn import() {
+        let global_args = parse_global_args(["zxc", "--import", "Makefile"]);
+        assert_eq!(global_args.import, Some(PathBuf::from("Makefile")));
+    }
+
+    #[test]
+    fn which() {
+        let global_args = parse_global_args(["zxc", "--which", "build"]);
+        assert_eq!(global_args.which.as_deref(), Some("build"));
+
+        let global_args = parse_global_args(["zxc", "--explain", "build"]);
+        assert_eq!(global_args.which.as_deref(), Some("build"));
+    }
+
+    #[test]
+    fn verbose() {
+        let global_args = parse_global_args(["zxc", "-v", "greet", "--verbose"]);
+        assert!(global_args.verbose);
+
+        let global_args = parse_global_args(["zxc", "greet", "-v"]);
+        assert!(!global_args.verbose);
+    }
+
+    #[test]
+    fn file_and_directory() {
+        let global_args =
+            parse_global_args(["zxc", "-d", "../other", "-f", "ci/tasks.yml", "deploy"]);
+        assert_eq!(global_args.directory, Some(PathBuf::from("../other")));
+        assert_eq!(global_args.file, Some(PathBuf::from("ci/tasks.yml")));
+
+        let global_args = parse_global_args(["zxc", "--directory", "../other", "--check"]);
+        assert!(global_args.check);
+        assert_eq!(global_args.directory, Some(PathBuf::from("../other")));
+    }
+
+    #[test]
+    fn subcommand() {
+        let global_args = parse_global_args(["zxc", "greet", "--name", "world"]);
+        assert!(!global_args.check);
+    }
+
+    #[test]
+    fn unknown_option() {
+        let global_args = parse_global_args(["zxc", "--unknown", "--help"]);
+        assert!(!global_args.check);
+    }
+}
+
+#[cfg(test)]
+mod build_cli_tests {
+    use super::{build_cli, describe_resolved_command};
+    use crate::yaml_command_defs::{DefinitionScope, YamlExampleDef};
+    use crate::zxc_command_defs::{
+        ArgumentType, Choices, DefaultValue, FlagType, ZxcArgumentDef, ZxcCommandDef,
+    };
+    use serial_test::serial;
+    use std::env;
+
+    #[test]
+    fn global_commands_section() {
+        let zxc_command_defs = vec![
+            ZxcCommandDef {
+                name: "build".to_string(),
+                command: "cargo build".to_string(),
+                description: Some("Build the project.".to_string()),
+                ..Default::default()
+            },
+            ZxcCommandDef {
+                name: "weather".to_string(),
+                command: "curl wttr.in".to_string(),
+                description: Some("Show weather.\nUses wttr.in.".to_string()),
+                scope: DefinitionScope::Global,
+                ..Default::default()
+            },
+        ];
+
+        let mut cli_command = build_cli(&zxc_command_defs);
+        let help = cli_command.render_help().to_string();
+        let (commands, global_commands) = help.split_once("Global commands:").unwrap();
+        assert!(commands.contains("build"));
+        assert!(!commands.contains("weather"));
+        assert!(global_commands.contains("weather  Show weather."));
+        assert!(!global_commands.contains("wttr.in"));
+
+        // Global commands can be run.
+        let matches = cli_command
+            .try_get_matches_from(["zxc", "weather"])
+            .unwrap();
+        assert_eq!(matches.subcommand_name(), Some("weather"));
+    }
+
+    #[test]
+    fn command_metadata() {
+        let zxc_command_defs = vec![
+            ZxcCommandDef {
+                name: "build".to_string(),
+                command: "cargo build".to_string(),
+                description: Some("Build the project.".to_string()),
+                deprecated: Some("Use 'compile' instead.".to_string()),
+                ..Default::default()
+            },
+            ZxcCommandDef {
+                name: "debug-dump".to_string(),
+                command: "env".to_string(),
+                hidden: true,
+                ..Default::default()
+            },
+            ZxcCommandDef {
+                name: "prepare".to_string(),
+                command: "mkdir -p out".to_string(),
+                private: true,
+                ..Default::default()
+            },
+        ];
+
+        let mut cli_command = build_cli(&zxc_command_defs);
+        let help = cli_command.render_help().to_string();
+        assert!(help.contains("build  [deprecated] Build the project."));
+        assert!(!help.contains("debug-dump"));
+        assert!(!help.contains("prepare"));
+
+        let subcommand = cli_command.find_subcommand_mut("build").unwrap();
+        let help = subcommand.render_long_help().to_string();
+        assert!(help.contains("Deprecated: Use 'compile' instead."));
+
+        // Hidden commands can be run.
+        let matches = cli_command
+            .try_get_matches_from(["zxc", "debug-dump"])
+            .unwrap();
+        assert_eq!(matches.subcommand_name(), Some("debug-dump"));
+    }
+
+    #[test]
+    fn command_groups() {
+        let zxc_command_defs = vec![
+            ZxcCommandDef {
+                name: "build".to_string(),
+                command: "cargo build".to_string(),
+                ..Default::default()
+            },
+            ZxcCommandDef {
+                name: "deploy".to_string(),
+                command: "./deploy.sh".to_string(),
+                description: Some("Deploy the project.".to_string()),
+                group: Some("Operations".to_string()),
+                ..Default::default()
+            },
+            ZxcCommandDef {
+                name: "weather".to_string(),
+                command: "curl wttr.in".to_string(),
+                scope: DefinitionScope::Global,
+                ..Default::default()
+            },
+        ];
+
+        let mut cli_command = build_cli(&zxc_command_defs);
+        let help = cli_command.render_help().to_string();
+        let (commands, sections) = help.split_once("Operations:").unwrap();
+        assert!(commands.contains("build"));
+        assert!(!commands.contains("deploy"));
+        let (operations, global_commands) = sections.split_once("Global commands:").unwrap();
+        assert!(operations.contains("deploy  Deploy the project."));
+        assert!(global_commands.contains("weather"));
+    }
+
+    #[test]
+    fn long_help() {
+        let zxc_command_defs = vec![ZxcCommandDef {
+            name: "deploy".to_string(),
+            command: "./scripts/deploy.sh --all".to_string(),
+            description: Some("Deploy the project.".to_string()),
+            help: Some("Deploys all services.\nRequires credentials.".to_string()),
+            examples: vec![YamlExampleDef {
+                command: "zxc deploy".to_string(),
+                description: Some("Deploy to staging.".to_string()),
+            }],
+            hide_command: true,
+            ..Default::default()
+        }];
+
+        let mut cli_command = build_cli(&zxc_command_defs);
+        let subcommand = cli_command.find_subcommand_mut("deploy").unwrap();
+        let short_help = subcommand.render_help().to_string();
+        assert!(short_help.contains("Deploy the project."));
+        assert!(!short_help.contains("Requires credentials."));
+        assert!(!short_help.contains("Examples:"));
+
+        let long_help = subcommand.render_long_help().to_string();
+        assert!(
+            long_help
+                .contains("Deploy the project.\n\nDeploys all services.\nRequires credentials.")
+        );
+        assert!(long_help.contains("Examples:\n  zxc deploy\n      Deploy to staging."));
+        assert!(!long_help.contains("deploy.sh"));
+    }
+
+    #[test]
+    #[serial]
+    fn argument_env() {
+        let zxc_command_defs = vec![ZxcCommandDef {
+            name: "deploy".to_string(),
+            command: "./deploy.sh {{ target }} {{ region }} {{ mode }}".to_string(),
+            arguments: vec![
+                ZxcArgumentDef {
+                    name: "target".to_string(),
+                    flags: vec![ArgumentType::Named(FlagType::Long("target".to_string()))],
+                    default: Some(DefaultValue::Static("staging".to_string())),
+                    env: Some("ZXC_TEST_DEPLOY_TARGET".to_string()),
+                    ..Default::default()
+                },
+                ZxcArgumentDef {
+                    name: "region".to_string(),
+                    flags: vec![ArgumentType::Named(FlagType::Long("region".to_string()))],
+                    env: Some("ZXC_TEST_DEPLOY_REGION".to_string()),
+                    ..Default::default()
+                },
+                ZxcArgumentDef {
+                    name: "mode".to_string(),
+                    flags: vec![ArgumentType::Named(FlagType::Long("mode".to_string()))],
+                    default: Some(DefaultValue::Static("fast".to_string())),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }];
+        unsafe {
+            env::set_var("ZXC_TEST_DEPLOY_TARGET", "production");
+            env::set_var("ZXC_TEST_DEPLOY_REGION", "eu");
+        }
+
+        let mut cli_command = build_cli(&zxc_command_defs);
+        let subcommand = cli_command.find_subcommand_mut("deploy").unwrap();
+        let help = subcommand.render_help().to_string();
+        assert!(help.contains("[env: ZXC_TEST_DEPLOY_TARGET=production]"));
+
+        // Environment variable takes precedence over default value.
+        let matches = cli_command
+            .try_get_matches_from(["zxc", "deploy", "--region", "us"])
+            .unwrap();
+        let (_, subcommand_args) = matches.subcommand().unwrap();
+        let arguments = ["target", "region", "mode"]
+            .into_iter()
+            .map(|x| {
+                let value = subcommand_args.get_one::<String>(x).unwrap();
+                (x.to_string(), value.to_string())
+            })
+            .collect();
+        let description = describe_resolved_command(
+            &zxc_command_defs[0],
+            subcommand_args,
+            &arguments,
+            "./deploy.sh",
+        );
+        assert_eq!(
+            description,
+            "command: ./deploy.sh\n\
+             arguments:\n  \
+             target: production ($ZXC_TEST_DEPLOY_TARGET)\n  \
+             region: us (command line)\n  \
+             mode: fast (default)\n"
+        );
+
+        // Environment variable is used when argument has no default value.
+        unsafe { env::remove_var("ZXC_TEST_DEPLOY_TARGET") };
+        let matches = build_cli(&zxc_command_defs)
+            .try_get_matches_from(["zxc", "deploy"])
+            .unwrap();
+        let (_, subcommand_args) = matches.subcommand().unwrap();
+        assert_eq!(
+            subcommand_args.get_one::<String>("target").unwrap(),
+            "staging"
+        );
+        assert_eq!(subcommand_args.get_one::<String>("region").unwrap(), "eu");
+        unsafe { env::remove_var("ZXC_TEST_DEPLOY_REGION") };
+    }
+
+    #[test]
+    fn argument_choices() {
+        let zxc_command_defs = vec![ZxcCommandDef {
+            name: "build".to_string(),
+            command: "cargo build --profile {{ profile }} -p {{ package }}".to_string(),
+            arguments: vec![
+                ZxcArgumentDef {
+                    name: "profile".to_string(),
+                    flags: vec![ArgumentType::Named(FlagType::Long("profile".to_string()))],
+                    choices: Some(Choices::Values(vec![
+                        "debug".to_string(),
+                        "release".to_string(),
+                    ])),
+                    ..Default::default()
+                },
+                ZxcArgumentDef {
+                    name: "package".to_string(),
+                    flags: vec![ArgumentType::Positional],
+                    description: Some("Package to build.".to_string()),
+                    choices: Some(Choices::Shell {
+                        command: "cargo metadata".to_string(),
+                        cache: None,
+                    }),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }];
+
+        let mut cli_command = build_cli(&zxc_command_defs);
+        let subcommand = cli_command.find_subcommand_mut("build").unwrap();
+        let help = subcommand.render_help().to_string();
+        assert!(help.contains("[possible values: debug, release]"));
+        assert!(help.contains("Package to build. [possible values: $(cargo metadata)]"));
+
+        // Static choices are checked by parser.
+        let result =
+            cli_command
+                .clone()
+                .try_get_matches_from(["zxc", "build", "--profile", "fast"]);
+        assert!(result.is_err());
+
+        // Arguments with choices are not required - they're selected interactively.
+        let result = cli_command.try_get_matches_from(["zxc", "build"]);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn argument_relations() {
+        let argument_def = |name: &str| ZxcArgumentDef {
+            name: name.to_string(),
+            flags: vec![ArgumentType::Named(FlagType::Long(name.to_string()))],
+            ..Default::default()
+        };
+        let zxc_command_defs = vec![ZxcCommandDef {
+            name: "fetch".to_string(),
+            command: "./fetch.sh".to_string(),
+            arguments: vec![
+                argument_def("file"),
+                argument_def("url"),
+                argument_def("token"),
+                argument_def("quiet"),
+                argument_def("verbose"),
+            ],
+            conflicts: vec![vec!["quiet".to_string(), "verbose".to_string()]],
+            requires: [("url".to_string(), vec!["token".to_string()])].into(),
+            one_of: vec![vec!["file".to_string(), "url".to_string()]],
+            ..Default::default()
+        }];
+        let cli_command = build_cli(&zxc_command_defs);
+        let parse = |args: &[&str]| {
+            let args = ["zxc", "fetch"].iter().chain(args);
+            cli_command.clone().try_get_matches_from(args)
+        };
+
+        assert!(parse(&["--file", "a"]).is_ok());
+        assert!(parse(&["--url", "a", "--token", "t"]).is_ok());
+        // Exactly one of the group is required.
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["--file", "a", "--url", "b", "--token", "t"]).is_err());
+        // Required argument is missing.
+        assert!(parse(&["--url", "a"]).is_err());
+        // Conflicting arguments.
+        assert!(parse(&["--file", "a", "--quiet", "1", "--verbose", "1"]).is_err());
+    }
+
+    #[test]
+    fn argument_constraints() {
+        let zxc_command_defs = vec![ZxcCommandDef {
+            name: "serve".to_string(),
+            command: "./serve.sh {{ port }}".to_string(),
+            arguments: vec![ZxcArgumentDef {
+                name: "port".to_string(),
+                flags: vec![ArgumentType::Named(FlagType::Long("port".to_string()))],
+                default: Some(DefaultValue::Static("8080".to_string())),
+                description: Some("Port.".to_string()),
+                min: Some(1.0),
+                max: Some(65535.0),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }];
+        let mut cli_command = build_cli(&zxc_command_defs);
+        let subcommand = cli_command.find_subcommand_mut("serve").unwrap();
+        let help = subcommand.render_help().to_string();
+        assert!(help.contains("Port. [min: 1] [max: 65535]"));
+
+        let result = cli_command
+            .clone()
+            .try_get_matches_from(["zxc", "serve", "--port", "0"]);
+        assert!(result.is_err_and(|e| e.to_string().contains("value must be at least 1")));
+        let result = cli_command.try_get_matches_from(["zxc", "serve", "--port", "80"]);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn flag_aliases() {
+        let zxc_command_defs = vec![ZxcCommandDef {
+            name: "deploy".to_string(),
+            command: "./deploy.sh {{ env }}".to_string(),
+            arguments: vec![ZxcArgumentDef {
+                name: "env".to_string(),
+                flags: vec![
+                    ArgumentType::Named(FlagType::Long("env".to_string())),
+                    ArgumentType::Named(FlagType::Long("environment".to_string())),
+                    ArgumentType::Named(FlagType::Short("e".to_string())),
+                ],
+                hidden_flags: vec![FlagType::Long("stage".to_string())],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }];
+        let mut cli_command = build_cli(&zxc_command_defs);
+        let subcommand = cli_command.find_subcommand_mut("deploy").unwrap();
+        let help = subcommand.render_help().to_string();
+        assert!(help.contains("-e, --env <env>  [alias: --environment]"));
+        assert!(!help.contains("--stage"));
+
+        for flag in ["--env", "--environment", "-e", "--stage"] {
+            let matches = cli_command
+                .clone()
+                .try_get_matches_from(["zxc", "deploy", flag, "prod"])
+                .unwrap();
+            let (_, subcommand_args) = matches.subcommand().unwrap();
+            assert_eq!(subcommand_args.get_one::<String>("env").unwrap(), "prod");
+        }
+    }
+}
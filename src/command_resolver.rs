@@ -1,19 +1,49 @@
 use minijinja::{Environment, Error, UndefinedBehavior, Value};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
 
-/// Resolve shell command.
-pub fn resolve_command(
+use crate::command_runner::capture_command_output;
+use crate::error::ZxcError;
+use crate::zxc_command_defs::{Choices, DefaultValue, ZxcArgumentDef, ZxcCommandDef};
+
+const ENV_NAME: &str = "command";
+
+/// Create template environment containing command template.
+fn create_environment(
     unresolved_command: &str,
-    arguments: &BTreeMap<String, String>,
-) -> Result<String, Error> {
-    // Create environment and add command template.
-    const ENV_NAME: &str = "command";
+    undefined_behavior: UndefinedBehavior,
+) -> Result<Environment<'_>, Error> {
     let mut env = Environment::new();
-    env.set_undefined_behavior(UndefinedBehavior::Strict);
+    env.set_undefined_behavior(undefined_behavior);
     match env.add_template(ENV_NAME, unresolved_command) {
         Ok(_) => (),
         Err(e) => return Err(e),
     };
+    Ok(env)
+}
+
+/// Find variables referenced by shell command.
+/// Variables defined in the template itself and global functions are excluded.
+pub fn find_command_variables(unresolved_command: &str) -> Result<BTreeSet<String>, Error> {
+    let env = create_environment(unresolved_command, UndefinedBehavior::Strict)?;
+    let template = env.get_template(ENV_NAME)?;
+
+    let globals: BTreeSet<&str> = env.globals().map(|(name, _)| name).collect();
+    let variables = template
+        .undeclared_variables(false)
+        .into_iter()
+        .filter(|x| !globals.contains(x.as_str()))
+        .collect();
+    Ok(variables)
+}
+
+/// Resolve shell command.
+pub fn resolve_command(
+    unresolved_command: &str,
+    arguments: &BTreeMap<String, String>,
+) -> Result<String, Error> {
+    // Create environment and add command template.
+    let env = create_environment(unresolved_command, UndefinedBehavior::Strict)?;
 
     // Get command template.
     let template = env.get_template(ENV_NAME)?;
@@ -23,6 +53,121 @@ pub fn resolve_command(
     template.render(ctx)
 }
 
+/// Resolve template of a command.
+/// Missing arguments used in relations are optional - they're undefined, so they're rendered
+/// as empty string and can be tested by `is defined`. Other missing arguments are an error.
+pub fn resolve_command_template(
+    zxc_command_def: &ZxcCommandDef,
+    template: &str,
+    arguments: &BTreeMap<String, String>,
+) -> Result<String, Error> {
+    // Missing required argument is reported by strict rendering.
+    let variables = find_command_variables(template)?;
+    let is_missing_required = variables
+        .iter()
+        .any(|x| !arguments.contains_key(x) && !zxc_command_def.is_argument_related(x));
+    if is_missing_required {
+        return resolve_command(template, arguments);
+    }
+
+    // Create environment and add command template.
+    let env = create_environment(template, UndefinedBehavior::Lenient)?;
+
+    // Get command template.
+    let template = env.get_template(ENV_NAME)?;
+
+    // Render output string based on provided arguments.
+    let ctx = Value::from_iter(arguments.iter());
+    template.render(ctx)
+}
+
+/// Resolve dynamic default values of arguments which are not provided.
+/// Values of arguments with choices and without default value are selected by provided function,
+/// unless they're used in relations.
+/// Values are resolved once values of all referenced arguments are known - default values
+/// can reference selected values and choices can reference default values.
+/// Output of shell commands is used without trailing newline.
+pub fn resolve_default_values<F>(
+    zxc_command_def: &ZxcCommandDef,
+    arguments: &mut BTreeMap<String, String>,
+    shell: &str,
+    working_directory: &Path,
+    env: &BTreeMap<String, String>,
+    mut select: F,
+) -> Result<(), ZxcError>
+where
+    F: FnMut(&ZxcArgumentDef, &BTreeMap<String, String>) -> Result<String, ZxcError>,
+{
+    let mut pending: Vec<(&ZxcArgumentDef, Option<&str>)> = zxc_command_def
+        .arguments
+        .iter()
+        .filter(|x| !arguments.contains_key(&x.name))
+        .filter_map(|x| match (&x.default, &x.choices) {
+            (Some(default), _) => Some((x, Some(default.template()?))),
+            (None, Some(_)) if zxc_command_def.is_argument_related(&x.name) => None,
+            (None, Some(Choices::Shell { command, .. })) => Some((x, Some(command.as_str()))),
+            (None, Some(Choices::Values(_))) => Some((x, None)),
+            (None, None) => None,
+        })
+        .collect();
+
+    while !pending.is_empty() {
+        // Pick value not referencing pending ones.
+        // Otherwise, resolve the first one - it reports missing reference.
+        let is_pending = |name: &String| pending.iter().any(|(x, _)| &x.name == name);
+        let index = pending
+            .iter()
+            .position(|(_, template)| match template.map(find_command_variables) {
+                Some(Ok(x)) => !x.iter().any(is_pending),
+                Some(Err(_)) | None => true,
+            })
+            .unwrap_or(0);
+        let (zxc_argument_def, template) = pending.remove(index);
+        let name = &zxc_argument_def.name;
+
+        // Select value from choices.
+        let (default, template) = match (&zxc_argument_def.default, template) {
+            (Some(default), Some(template)) => (default, template),
+            _ => {
+                let value = select(zxc_argument_def, arguments)?;
+                arguments.insert(name.clone(), value);
+                continue;
+            }
+        };
+
+        // Resolve template.
+        let resolved = match resolve_command_template(zxc_command_def, template, arguments) {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(
+                    ZxcError::from_template_error(&zxc_command_def.name, template, &e)
+                        .with_origin(zxc_command_def.origin.as_ref()),
+                );
+            }
+        };
+
+        // Run shell command and capture its output.
+        let value = match default {
+            DefaultValue::Shell(_) => {
+                match capture_command_output(&resolved, shell, working_directory, env) {
+                    Ok(x) => x.trim_end_matches(['\n', '\r']).to_string(),
+                    Err(e) => {
+                        return Err(ZxcError::Execution {
+                            command: zxc_command_def.name.clone(),
+                            source: std::io::Error::other(format!(
+                                "default value of argument '{name}': {e}"
+                            )),
+                        });
+                    }
+                }
+            }
+            _ => resolved,
+        };
+        arguments.insert(name.clone(), value);
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod resolve_command_tests {
     use std::collections::BTreeMap;
@@ -90,3 +235,218 @@ mod resolve_command_tests {
         assert!(result.is_ok_and(|v| v == "echo hello"));
     }
 }
+
+#[cfg(test)]
+mod find_command_variables_tests {
+    use super::find_command_variables;
+    use minijinja::ErrorKind;
+
+    #[test]
+    fn variables() {
+        let unresolved_command = "echo {{ arg1 }} {{ arg2 | upper }} {{ arg1 }}";
+
+        let result = find_command_variables(unresolved_command).unwrap();
+        assert_eq!(result.into_iter().collect::<Vec<_>>(), vec!["arg1", "arg2"]);
+    }
+
+    #[test]
+    fn local_and_global_names() {
+        let unresolved_command =
+            "{% for i in range(count | int) %}{% set x = i %}echo {{ x }};{% endfor %}";
+
+        let result = find_command_variables(unresolved_command).unwrap();
+        assert_eq!(result.into_iter().collect::<Vec<_>>(), vec!["count"]);
+    }
+
+    #[test]
+    fn invalid_template_syntax() {
+        let unresolved_command = "echo {{ arg1 {{ arg2 }}";
+
+        let result = find_command_variables(unresolved_command);
+        assert!(result.is_err_and(|e| e.kind() == ErrorKind::SyntaxError));
+    }
+}
+
+#[cfg(test)]
+mod resolve_command_template_tests {
+    use super::resolve_command_template;
+    use crate::zxc_command_defs::ZxcCommandDef;
+    use minijinja::ErrorKind;
+    use std::collections::BTreeMap;
+
+    fn command_def() -> ZxcCommandDef {
+        ZxcCommandDef {
+            name: "excl".to_string(),
+            conflicts: vec![vec!["a".to_string(), "b".to_string()]],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn missing_related_argument() {
+        let mut arguments = BTreeMap::new();
+        arguments.insert("a".to_string(), "1".to_string());
+
+        let result =
+            resolve_command_template(&command_def(), "echo a={{ a }} b={{ b }}", &arguments);
+        assert_eq!(result.unwrap(), "echo a=1 b=");
+
+        let template = "echo{% if a is defined %} -a {{ a }}{% endif %}{% if b is defined %} -b {{ b }}{% endif %}";
+        let result = resolve_command_template(&command_def(), template, &arguments);
+        assert_eq!(result.unwrap(), "echo -a 1");
+    }
+
+    #[test]
+    fn missing_required_argument() {
+        let mut arguments = BTreeMap::new();
+        arguments.insert("a".to_string(), "1".to_string());
+
+        let result = resolve_command_template(&command_def(), "echo {{ a }} {{ c }}", &arguments);
+        assert!(result.is_err_and(|e| e.kind() == ErrorKind::UndefinedError));
+    }
+}
+
+#[cfg(test)]
+mod resolve_default_values_tests {
+    use super::resolve_default_values;
+    use crate::error::ZxcError;
+    use crate::settings::default_shell;
+    use crate::zxc_command_defs::{
+        ArgumentType, Choices, DefaultValue, ZxcArgumentDef, ZxcCommandDef,
+    };
+    use std::collections::BTreeMap;
+    use std::path::Path;
+
+    fn argument_def(name: &str, default: Option<DefaultValue>) -> ZxcArgumentDef {
+        ZxcArgumentDef {
+            name: name.to_string(),
+            flags: vec![ArgumentType::Positional],
+            default,
+            ..Default::default()
+        }
+    }
+
+    fn command_def(arguments: Vec<ZxcArgumentDef>) -> ZxcCommandDef {
+        ZxcCommandDef {
+            name: "deploy".to_string(),
+            command: "echo".to_string(),
+            arguments,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn template_and_shell() {
+        // Default values are resolved after values they reference.
+        let zxc_command_def = command_def(vec![
+            argument_def(
+                "image",
+                Some(DefaultValue::Shell("echo {{ service }}:latest".to_string())),
+            ),
+            argument_def("name", None),
+            argument_def(
+                "service",
+                Some(DefaultValue::Template("{{ name }}-svc".to_string())),
+            ),
+        ]);
+        let mut arguments = BTreeMap::new();
+        arguments.insert("name".to_string(), "api".to_string());
+
+        let result = resolve_default_values(
+            &zxc_command_def,
+            &mut arguments,
+            &default_shell(),
+            Path::new("."),
+            &BTreeMap::new(),
+            |_, _| unreachable!("no value is selected"),
+        );
+        assert!(result.is_ok());
+        assert_eq!(arguments.get("service").unwrap(), "api-svc");
+        assert_eq!(arguments.get("image").unwrap(), "api-svc:latest");
+    }
+
+    #[test]
+    fn selected_value() {
+        // Default value references value selected from choices.
+        let mut service = argument_def("service", None);
+        service.choices = Some(Choices::Values(vec!["api".to_string()]));
+        let zxc_command_def = command_def(vec![
+            argument_def(
+                "image",
+                Some(DefaultValue::Template("{{ service }}:latest".to_string())),
+            ),
+            service,
+        ]);
+        let mut arguments = BTreeMap::new();
+
+        let result = resolve_default_values(
+            &zxc_command_def,
+            &mut arguments,
+            &default_shell(),
+            Path::new("."),
+            &BTreeMap::new(),
+            |x, _| Ok(format!("{}-selected", x.name)),
+        );
+        assert!(result.is_ok());
+        assert_eq!(arguments.get("service").unwrap(), "service-selected");
+        assert_eq!(arguments.get("image").unwrap(), "service-selected:latest");
+    }
+
+    #[test]
+    fn provided_argument() {
+        let zxc_command_def = command_def(vec![argument_def(
+            "tag",
+            Some(DefaultValue::Shell("exit 1".to_string())),
+        )]);
+        let mut arguments = BTreeMap::new();
+        arguments.insert("tag".to_string(), "v1".to_string());
+
+        // Shell command is not run if argument is provided.
+        let result = resolve_default_values(
+            &zxc_command_def,
+            &mut arguments,
+            &default_shell(),
+            Path::new("."),
+            &BTreeMap::new(),
+            |_, _| unreachable!("no value is selected"),
+        );
+        assert!(result.is_ok());
+        assert_eq!(arguments.get("tag").unwrap(), "v1");
+    }
+
+    #[test]
+    fn failing_shell_command() {
+        let zxc_command_def = command_def(vec![argument_def(
+            "tag",
+            Some(DefaultValue::Shell("exit 1".to_string())),
+        )]);
+
+        let result = resolve_default_values(
+            &zxc_command_def,
+            &mut BTreeMap::new(),
+            &default_shell(),
+            Path::new("."),
+            &BTreeMap::new(),
+            |_, _| unreachable!("no value is selected"),
+        );
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Execution { .. })));
+    }
+
+    #[test]
+    fn undefined_reference() {
+        let zxc_command_def = command_def(vec![argument_def(
+            "service",
+            Some(DefaultValue::Template("{{ name }}-svc".to_string())),
+        )]);
+
+        let result = resolve_default_values(
+            &zxc_command_def,
+            &mut BTreeMap::new(),
+            &default_shell(),
+            Path::new("."),
+            &BTreeMap::new(),
+            |_, _| unreachable!("no value is selected"),
+        );
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Template { .. })));
+    }
+}
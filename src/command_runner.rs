@@ -1,7 +1,19 @@
+use crate::signal_forwarder::SignalForwarder;
+use crate::zxc_command_defs::{Backoff, ZxcRetryDef};
 use run_script::types::ScriptError::{Description, FsIOError, IOError};
-use run_script::{IoOptions, ScriptError, ScriptOptions, spawn};
+use run_script::{IoOptions, ScriptError, ScriptOptions, run};
+use std::collections::{BTreeMap, HashMap};
 use std::io::Error;
+use std::path::Path;
+#[cfg(unix)]
+use std::process::Command;
 use std::process::ExitStatus;
+use std::thread::sleep;
+use std::time::Duration;
+
+/// Name of environment variable containing name of run command.
+/// It's set for spawned processes, so nested `zxc` calls can run private commands.
+pub const COMMAND_ENV_NAME: &str = "ZXC_COMMAND";
 
 /// Convert all errors to `std::io::Error`.
 fn match_error(script_error: ScriptError) -> Error {
@@ -13,22 +25,174 @@ fn match_error(script_error: ScriptError) -> Error {
 }
 
 /// Spawn new process based on provided resolved command string.
-pub fn run_command(command: &str) -> Result<ExitStatus, Error> {
+/// Command is run using specified shell, in specified working directory.
+/// Provided environment variables are added to inherited ones.
+/// On Unix, process is started in its own process group, see `SignalForwarder`.
+pub fn run_command(
+    command: &str,
+    shell: &str,
+    working_directory: &Path,
+    env: &BTreeMap<String, String>,
+) -> Result<ExitStatus, Error> {
+    // Signals received from now on are forwarded to the process.
+    let signal_forwarder = SignalForwarder::new();
+
+    // Spawn process.
+    #[cfg(unix)]
+    let spawn_result = {
+        let mut process = Command::new(shell);
+        process
+            .arg("-c")
+            .arg(command)
+            .current_dir(working_directory)
+            .envs(env);
+        signal_forwarder.prepare(&mut process);
+        process.spawn()
+    };
+    #[cfg(not(unix))]
+    let spawn_result = {
+        let args = vec![];
+        let mut options = ScriptOptions::new();
+        options.runner = Some(shell.to_string());
+        options.working_directory = Some(working_directory.to_path_buf());
+        if !env.is_empty() {
+            options.env_vars = Some(HashMap::from_iter(env.clone()));
+        }
+        options.input_redirection = IoOptions::Inherit;
+        options.output_redirection = IoOptions::Inherit;
+        run_script::spawn(command, &args, &options).map_err(match_error)
+    };
+    let mut child = spawn_result?;
+
+    // Wait for process to finish.
+    signal_forwarder.wait(&mut child)
+}
+
+/// Run command and capture its standard output.
+/// Command is run using specified shell, in specified working directory.
+/// Failure is reported along with standard error of the process.
+pub fn capture_command_output(
+    command: &str,
+    shell: &str,
+    working_directory: &Path,
+    env: &BTreeMap<String, String>,
+) -> Result<String, Error> {
     // Set parameters.
     let args = vec![];
     let mut options = ScriptOptions::new();
-    options.input_redirection = IoOptions::Inherit;
-    options.output_redirection = IoOptions::Inherit;
+    options.runner = Some(shell.to_string());
+    options.working_directory = Some(working_directory.to_path_buf());
+    if !env.is_empty() {
+        options.env_vars = Some(HashMap::from_iter(env.clone()));
+    }
+    options.output_redirection = IoOptions::Pipe;
 
-    // Spawn process.
-    let spawn_result = spawn(command, &args, &options);
-    let mut child = match spawn_result {
-        Ok(child) => child,
+    // Run process and wait for its output.
+    let (code, output, error) = match run(command, &args, &options) {
+        Ok(x) => x,
         Err(e) => return Err(match_error(e)),
     };
+    if code != 0 {
+        let error = error.trim();
+        return Err(match error.is_empty() {
+            true => Error::other(format!("`{command}` failed with exit code {code}")),
+            false => Error::other(format!("`{command}` failed with exit code {code}: {error}")),
+        });
+    }
+    Ok(output)
+}
 
-    // Wait for process to finish.
-    child.wait()
+/// Get exit code based on process exit status.
+/// Process terminated by a signal results in `128 + <signal number>`, as in shells.
+pub fn exit_code(status: &ExitStatus) -> i32 {
+    if let Some(code) = status.code() {
+        return code;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+
+    1
+}
+
+/// Delay before retrying after specified failed attempt (counted from 1).
+fn retry_delay(retry: &ZxcRetryDef, attempt: u32) -> Duration {
+    match retry.backoff {
+        Backoff::Constant => retry.delay,
+        Backoff::Exponential => retry.delay.saturating_mul(2u32.saturating_pow(attempt - 1)),
+    }
+}
+
+/// Check if command should be retried based on its exit status.
+fn should_retry(retry: &ZxcRetryDef, status: &ExitStatus) -> bool {
+    if status.success() {
+        return false;
+    }
+    match status.code() {
+        Some(code) => retry.on_exit_codes.is_empty() || retry.on_exit_codes.contains(&code),
+        // Process terminated by signal - don't retry.
+        None => false,
+    }
+}
+
+/// Describe exit status for logging purposes.
+fn describe_status(status: &ExitStatus) -> String {
+    match status.code() {
+        Some(code) => format!("exit code {code}"),
+        None => "signal".to_string(),
+    }
+}
+
+/// Spawn new process based on provided resolved command string.
+/// Process is spawned again according to retry policy if it fails.
+pub fn run_command_with_retry(
+    command: &str,
+    shell: &str,
+    working_directory: &Path,
+    env: &BTreeMap<String, String>,
+    retry: &ZxcRetryDef,
+) -> Result<ExitStatus, Error> {
+    let mut attempt = 1;
+    loop {
+        let status = run_command(command, shell, working_directory, env)?;
+        if status.success() {
+            if attempt > 1 {
+                eprintln!(
+                    "zxc: command succeeded on attempt {attempt}/{}",
+                    retry.attempts
+                );
+            }
+            return Ok(status);
+        }
+
+        let status_description = describe_status(&status);
+        if !should_retry(retry, &status) {
+            eprintln!(
+                "zxc: attempt {attempt}/{} failed with {status_description}, not retrying",
+                retry.attempts
+            );
+            return Ok(status);
+        }
+        if attempt >= retry.attempts {
+            eprintln!(
+                "zxc: command failed after {attempt} attempts, last with {status_description}"
+            );
+            return Ok(status);
+        }
+
+        let delay = retry_delay(retry, attempt);
+        eprintln!(
+            "zxc: attempt {attempt}/{} failed with {status_description}, retrying in {delay:?}",
+            retry.attempts
+        );
+        sleep(delay);
+        attempt += 1;
+    }
 }
 
 #[cfg(test)]
@@ -74,13 +238,17 @@ mod match_error_tests {
 #[cfg(test)]
 mod run_command_tests {
     use super::run_command;
+    use crate::settings::default_shell;
+    use std::collections::BTreeMap;
     use std::env::consts::OS;
+    use std::path::Path;
+    use tempfile::tempdir;
 
     #[test]
     fn success() {
         let command = "echo Hello, World!";
 
-        let result = run_command(command);
+        let result = run_command(command, &default_shell(), Path::new("."), &BTreeMap::new());
         assert!(result.is_ok());
 
         let status = result.unwrap();
@@ -91,7 +259,7 @@ mod run_command_tests {
     fn failure() {
         let command = "(exit 1)";
 
-        let result = run_command(command);
+        let result = run_command(command, &default_shell(), Path::new("."), &BTreeMap::new());
         assert!(result.is_ok());
 
         let status = result.unwrap();
@@ -102,11 +270,286 @@ mod run_command_tests {
     fn non_existent_command() {
         let command = "non_existent_command";
 
-        let result = run_command(command);
+        let result = run_command(command, &default_shell(), Path::new("."), &BTreeMap::new());
         assert!(result.is_ok());
 
         let status = result.unwrap();
         let expected = if OS == "windows" { 1 } else { 127 };
         assert_eq!(status.code().unwrap(), expected);
     }
+
+    #[test]
+    fn custom_shell() {
+        // Skip test for Windows.
+        if OS == "windows" {
+            return;
+        }
+
+        let status = run_command(
+            "test \"$0\" != \"\" && exit 4",
+            "bash",
+            Path::new("."),
+            &BTreeMap::new(),
+        )
+        .unwrap();
+        assert_eq!(status.code().unwrap(), 4);
+
+        let result = run_command(
+            "echo",
+            "non_existent_shell",
+            Path::new("."),
+            &BTreeMap::new(),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn working_directory() {
+        // Skip test for Windows.
+        if OS == "windows" {
+            return;
+        }
+
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("marker"), "").unwrap();
+
+        let status = run_command(
+            "test -f marker",
+            &default_shell(),
+            dir.path(),
+            &BTreeMap::new(),
+        )
+        .unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn environment_variables() {
+        // Skip test for Windows.
+        if OS == "windows" {
+            return;
+        }
+
+        let env = BTreeMap::from([("ZXC_TEST_VALUE".to_string(), "expected".to_string())]);
+        let command = "test \"$ZXC_TEST_VALUE\" = expected && test -n \"$PATH\"";
+        let status = run_command(command, &default_shell(), Path::new("."), &env).unwrap();
+        assert!(status.success());
+    }
+    #[test]
+    fn own_process_group() {
+        // Skip test for platforms without procfs.
+        if OS != "linux" {
+            return;
+        }
+
+        // Process group ID is the 5th field of process status.
+        let command = "set -- $(cat /proc/$$/stat); test \"$5\" = $$";
+        let status =
+            run_command(command, &default_shell(), Path::new("."), &BTreeMap::new()).unwrap();
+        assert!(status.success());
+    }
+}
+
+#[cfg(test)]
+mod exit_code_tests {
+    use super::{exit_code, run_command};
+    use crate::settings::default_shell;
+    use std::collections::BTreeMap;
+    use std::env::consts::OS;
+    use std::path::Path;
+
+    #[test]
+    fn normal_exit() {
+        let status = run_command(
+            "(exit 3)",
+            &default_shell(),
+            Path::new("."),
+            &BTreeMap::new(),
+        )
+        .unwrap();
+        assert_eq!(exit_code(&status), 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn terminated_by_signal() {
+        use std::os::unix::process::ExitStatusExt;
+        use std::process::ExitStatus;
+
+        // Raw wait status for process killed by SIGKILL (9).
+        let status = ExitStatus::from_raw(9);
+        assert_eq!(exit_code(&status), 137);
+    }
+
+    #[test]
+    fn command_terminated_by_signal() {
+        // Skip test for Windows.
+        if OS == "windows" {
+            return;
+        }
+
+        let status = run_command(
+            "kill -TERM $$",
+            &default_shell(),
+            Path::new("."),
+            &BTreeMap::new(),
+        )
+        .unwrap();
+        assert_eq!(exit_code(&status), 128 + 15);
+    }
+}
+
+#[cfg(test)]
+mod retry_delay_tests {
+    use super::retry_delay;
+    use crate::zxc_command_defs::{Backoff, ZxcRetryDef};
+    use std::time::Duration;
+
+    #[test]
+    fn constant() {
+        let retry = ZxcRetryDef {
+            attempts: 3,
+            delay: Duration::from_secs(2),
+            backoff: Backoff::Constant,
+            on_exit_codes: vec![],
+        };
+        assert_eq!(retry_delay(&retry, 1), Duration::from_secs(2));
+        assert_eq!(retry_delay(&retry, 2), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn exponential() {
+        let retry = ZxcRetryDef {
+            attempts: 4,
+            delay: Duration::from_secs(2),
+            backoff: Backoff::Exponential,
+            on_exit_codes: vec![],
+        };
+        assert_eq!(retry_delay(&retry, 1), Duration::from_secs(2));
+        assert_eq!(retry_delay(&retry, 2), Duration::from_secs(4));
+        assert_eq!(retry_delay(&retry, 3), Duration::from_secs(8));
+    }
+}
+
+#[cfg(test)]
+mod run_command_with_retry_tests {
+    use super::run_command_with_retry;
+    use crate::settings::default_shell;
+    use crate::zxc_command_defs::{Backoff, ZxcRetryDef};
+    use std::collections::BTreeMap;
+    use std::env::consts::OS;
+    use std::fs;
+    use std::path::Path;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn retry_def(attempts: u32, on_exit_codes: Vec<i32>) -> ZxcRetryDef {
+        ZxcRetryDef {
+            attempts,
+            delay: Duration::ZERO,
+            backoff: Backoff::Constant,
+            on_exit_codes,
+        }
+    }
+
+    #[test]
+    fn success_after_retry() {
+        // Skip test for Windows.
+        if OS == "windows" {
+            return;
+        }
+
+        // Command fails on first run only - marker file is created then.
+        let dir = tempdir().unwrap();
+        let marker = dir.path().join("marker");
+        let command = format!("test -f {0} || (touch {0}; exit 1)", marker.display());
+
+        let result = run_command_with_retry(
+            &command,
+            &default_shell(),
+            Path::new("."),
+            &BTreeMap::new(),
+            &retry_def(3, vec![]),
+        );
+        assert!(result.is_ok_and(|s| s.success()));
+        assert!(marker.exists());
+    }
+
+    #[test]
+    fn attempts_exhausted() {
+        // Skip test for Windows.
+        if OS == "windows" {
+            return;
+        }
+
+        // Each attempt appends a line to the counter file.
+        let dir = tempdir().unwrap();
+        let counter = dir.path().join("counter");
+        let command = format!("echo x >> {}; exit 75", counter.display());
+
+        let result = run_command_with_retry(
+            &command,
+            &default_shell(),
+            Path::new("."),
+            &BTreeMap::new(),
+            &retry_def(3, vec![1, 75]),
+        );
+        assert!(result.is_ok_and(|s| s.code() == Some(75)));
+        assert_eq!(fs::read_to_string(counter).unwrap().lines().count(), 3);
+    }
+
+    #[test]
+    fn exit_code_not_retried() {
+        // Skip test for Windows.
+        if OS == "windows" {
+            return;
+        }
+
+        let dir = tempdir().unwrap();
+        let counter = dir.path().join("counter");
+        let command = format!("echo x >> {}; exit 2", counter.display());
+
+        let result = run_command_with_retry(
+            &command,
+            &default_shell(),
+            Path::new("."),
+            &BTreeMap::new(),
+            &retry_def(3, vec![1, 75]),
+        );
+        assert!(result.is_ok_and(|s| s.code() == Some(2)));
+        assert_eq!(fs::read_to_string(counter).unwrap().lines().count(), 1);
+    }
+}
+
+#[cfg(test)]
+mod capture_command_output_tests {
+    use super::capture_command_output;
+    use crate::settings::default_shell;
+    use std::collections::BTreeMap;
+    use std::path::Path;
+
+    #[test]
+    fn success() {
+        let mut env = BTreeMap::new();
+        env.insert("ZXC_TEST_NAME".to_string(), "World".to_string());
+
+        let result = capture_command_output(
+            "echo Hello, $ZXC_TEST_NAME!",
+            &default_shell(),
+            Path::new("."),
+            &env,
+        );
+        assert!(result.is_ok_and(|x| x.trim() == "Hello, World!"));
+    }
+
+    #[test]
+    fn failure() {
+        let result = capture_command_output(
+            "echo oops >&2; exit 3",
+            &default_shell(),
+            Path::new("."),
+            &BTreeMap::new(),
+        );
+        assert!(result.is_err_and(|e| e.to_string().ends_with("exit code 3: oops")));
+    }
 }
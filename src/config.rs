@@ -1,14 +1,29 @@
 use std::env::consts::OS;
-use std::fs::create_dir;
+use std::ffi::OsString;
 use std::io::{Error, ErrorKind};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::error::ZxcError;
+use crate::settings::Settings;
+
+/// Name of user configuration file.
+const USER_CONFIG_NAME: &str = "config.yml";
 
 /// `zxc` app configuration.
+#[derive(Default)]
 pub struct Config {
     /// Current working directory.
     pub cwd: PathBuf,
     /// `zxc` app home directory.
     pub app_home: PathBuf,
+    /// User home directory, if known.
+    pub home_dir: Option<PathBuf>,
+    /// Directory for cached data, if known.
+    pub cache_dir: Option<PathBuf>,
+    /// User settings.
+    pub settings: Settings,
+    /// Explicitly selected definition file - discovery is skipped if set.
+    pub definition_file: Option<PathBuf>,
 }
 
 fn home_env_name() -> &'static str {
@@ -19,39 +34,141 @@ fn home_env_name() -> &'static str {
     }
 }
 
+/// Find app home directory.
+/// - `$ZXC_HOME` - if set.
+/// - `$XDG_CONFIG_HOME/zxc` - `$<HOME>/.config/zxc` if `$XDG_CONFIG_HOME` is not set.
+/// - `$<HOME>/.zxc` - fallback, used only if exists and XDG directory doesn't.
+///
+/// Directory is not created if it doesn't exist.
+fn find_app_home<F>(env: F) -> Result<PathBuf, Error>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    // Explicit override.
+    if let Some(zxc_home) = env("ZXC_HOME") {
+        let app_home = PathBuf::from(zxc_home);
+        if app_home.exists() && !app_home.is_dir() {
+            return Err(Error::from(ErrorKind::NotADirectory));
+        }
+        return Ok(app_home);
+    }
+
+    // Get `$<HOME>`.
+    let env_name = home_env_name();
+    let home_dir = env(env_name).map(PathBuf::from);
+
+    // Get XDG app home - `$XDG_CONFIG_HOME/zxc`.
+    let xdg_config_home = match (env("XDG_CONFIG_HOME"), &home_dir) {
+        (Some(x), _) => PathBuf::from(x),
+        (None, Some(home_dir)) => home_dir.join(".config"),
+        (None, None) => {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("${env_name} not found"),
+            ));
+        }
+    };
+    let xdg_app_home = xdg_config_home.join("zxc");
+
+    // Fallback to `$<HOME>/.zxc`.
+    if !xdg_app_home.is_dir() {
+        let legacy_app_home = home_dir.map(|x| x.join(".zxc"));
+        if let Some(app_home) = legacy_app_home.filter(|x| x.is_dir()) {
+            return Ok(app_home);
+        }
+    }
+    Ok(xdg_app_home)
+}
+
+/// Find user configuration files, in order of precedence from the lowest.
+/// - `$<HOME>/.zxc/config.yml` - if it's not in app home, so it's not ignored when XDG directory exists.
+/// - `<app home>/config.yml`.
+///
+/// Only configuration file in app home is used if `$ZXC_HOME` is set.
+fn find_config_files<F>(app_home: &Path, env: F) -> Vec<PathBuf>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    let mut config_files = Vec::new();
+    if env("ZXC_HOME").is_none()
+        && let Some(home_dir) = env(home_env_name())
+    {
+        let legacy_app_home = PathBuf::from(home_dir).join(".zxc");
+        if legacy_app_home != app_home {
+            config_files.push(legacy_app_home.join(USER_CONFIG_NAME));
+        }
+    }
+    config_files.push(app_home.join(USER_CONFIG_NAME));
+    config_files
+}
+
+/// Find cache directory.
+/// - `$XDG_CACHE_HOME/zxc` - `$<HOME>/.cache/zxc` if `$XDG_CACHE_HOME` is not set.
+///
+/// Directory is not created if it doesn't exist.
+fn find_cache_dir<F>(env: F) -> Option<PathBuf>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    let xdg_cache_home = match env("XDG_CACHE_HOME") {
+        Some(x) => PathBuf::from(x),
+        None => PathBuf::from(env(home_env_name())?).join(".cache"),
+    };
+    Some(xdg_cache_home.join("zxc"))
+}
+
 impl Config {
     /// Create configuration.
-    /// If `$<HOME>>/.zxc` directory doesn't exist - create such.
-    pub fn new() -> Result<Self, Error> {
+    /// User settings are loaded from configuration files and `ZXC_*` environment variables.
+    pub fn new() -> Result<Self, ZxcError> {
         // Get current working directory.
         let cwd = std::env::current_dir()?;
 
-        // Get `$<HOME>`.
-        let env_name = home_env_name();
-        let home_env = match std::env::var(env_name) {
-            Ok(value) => value,
-            Err(_) => {
-                return Err(Error::new(
-                    ErrorKind::NotFound,
-                    format!("${env_name} not found"),
-                ));
-            }
-        };
+        // Get app home.
+        let app_home = find_app_home(|name| std::env::var_os(name))?;
+        let home_dir = std::env::var_os(home_env_name()).map(PathBuf::from);
+        let cache_dir = find_cache_dir(|name| std::env::var_os(name));
 
-        // Get app home - `$<HOME>/.zxc`.
-        let app_home = PathBuf::from(home_env).join(".zxc");
+        // Load user settings.
+        let config_files = find_config_files(&app_home, |name| std::env::var_os(name));
+        let settings = Settings::load(&config_files, |name| std::env::var(name).ok())?;
 
-        // Create app home if not found.
-        if !app_home.exists() {
-            match create_dir(&app_home) {
-                Ok(_) => (),
-                Err(e) => return Err(e),
-            }
-        } else if !app_home.is_dir() {
-            return Err(Error::from(ErrorKind::NotADirectory));
+        Ok(Config {
+            cwd,
+            app_home,
+            home_dir,
+            cache_dir,
+            settings,
+            definition_file: None,
+        })
+    }
+
+    /// Change working directory used for discovery and running commands.
+    /// Relative path is resolved against current working directory.
+    pub fn set_directory(&mut self, directory_path: &Path) -> Result<(), ZxcError> {
+        let directory_path = self.cwd.join(directory_path);
+        if !directory_path.is_dir() {
+            return Err(ZxcError::Discovery {
+                path: directory_path,
+                message: "directory not found".to_string(),
+            });
         }
+        self.cwd = match directory_path.canonicalize() {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(ZxcError::Io {
+                    path: Some(directory_path),
+                    source: e,
+                });
+            }
+        };
+        Ok(())
+    }
 
-        Ok(Config { cwd, app_home })
+    /// Select definition file explicitly, instead of discovering them.
+    /// Relative path is resolved against working directory.
+    pub fn set_definition_file(&mut self, file_path: &Path) {
+        self.definition_file = Some(self.cwd.join(file_path));
     }
 }
 
@@ -59,63 +176,137 @@ impl Config {
 mod config_tests {
     // Tests must be run serially, as they interact with filesystem and env vars.
     use super::{Config, home_env_name};
+    use crate::error::ZxcError;
     use serial_test::serial;
     use std::env;
     use std::env::consts::OS;
     use std::fs;
     use std::io::ErrorKind;
+    use std::path::Path;
     use tempfile::tempdir;
 
+    /// Set `$<HOME>` and remove variables affecting app home.
+    fn set_home(home_path: &Path) {
+        unsafe {
+            env::set_var(home_env_name(), home_path);
+            env::remove_var("ZXC_HOME");
+            env::remove_var("XDG_CONFIG_HOME");
+            env::remove_var("XDG_CACHE_HOME");
+        }
+    }
+
     #[test]
     #[serial]
-    fn create_app_home() {
+    fn app_home_not_created() {
         let temp_home = tempdir().unwrap();
         let home_path = temp_home.path();
-        let env_name = home_env_name();
-        unsafe { env::set_var(env_name, home_path) };
+        set_home(home_path);
+
+        let config = Config::new().unwrap();
+        assert_eq!(config.cwd, env::current_dir().unwrap());
+        assert_eq!(config.app_home, home_path.join(".config").join("zxc"));
+        assert!(!config.app_home.exists());
+        assert!(!home_path.join(".zxc").exists());
+        assert_eq!(config.cache_dir, Some(home_path.join(".cache").join("zxc")));
+        assert!(!home_path.join(".cache").exists());
+    }
+
+    #[test]
+    #[serial]
+    fn xdg_cache_home() {
+        let temp_home = tempdir().unwrap();
+        let home_path = temp_home.path();
+        set_home(home_path);
+
+        let cache_path = home_path.join("cache");
+        unsafe { env::set_var("XDG_CACHE_HOME", &cache_path) };
+
+        let config = Config::new().unwrap();
+        assert_eq!(config.cache_dir, Some(cache_path.join("zxc")));
+
+        unsafe { env::remove_var("XDG_CACHE_HOME") };
+    }
+
+    #[test]
+    #[serial]
+    fn legacy_app_home_exists() {
+        let temp_home = tempdir().unwrap();
+        let home_path = temp_home.path();
+        set_home(home_path);
 
         let zxc_path = home_path.join(".zxc");
-        assert!(!zxc_path.exists());
+        fs::create_dir(&zxc_path).unwrap();
+        assert!(zxc_path.exists() && zxc_path.is_dir());
 
         let config = Config::new().unwrap();
         assert_eq!(config.cwd, env::current_dir().unwrap());
         assert_eq!(config.app_home, zxc_path);
-        assert!(zxc_path.exists() && zxc_path.is_dir());
     }
 
     #[test]
     #[serial]
-    fn create_app_home_fail() {
+    fn zxc_home_not_a_directory() {
         let temp_home = tempdir().unwrap();
         let home_path = temp_home.path();
-        let env_name = home_env_name();
-        unsafe { env::set_var(env_name, home_path) };
+        set_home(home_path);
 
-        let zxc_path = home_path.join(".zxc");
+        let zxc_path = home_path.join("zxc_home");
         fs::File::create(&zxc_path).unwrap();
-        assert!(zxc_path.exists() && zxc_path.is_file());
+        unsafe { env::set_var("ZXC_HOME", &zxc_path) };
 
         let result = Config::new();
-        assert!(result.is_err_and(|e| e.kind() == ErrorKind::NotADirectory));
+        assert!(result.is_err_and(
+            |e| matches!(e, ZxcError::Io { source, .. } if source.kind() == ErrorKind::NotADirectory)
+        ));
 
-        fs::remove_file(zxc_path).unwrap();
+        unsafe { env::remove_var("ZXC_HOME") };
     }
 
     #[test]
     #[serial]
-    fn app_home_exists() {
+    fn user_settings() {
         let temp_home = tempdir().unwrap();
         let home_path = temp_home.path();
-        let env_name = home_env_name();
-        unsafe { env::set_var(env_name, home_path) };
+        set_home(home_path);
 
         let zxc_path = home_path.join(".zxc");
         fs::create_dir(&zxc_path).unwrap();
-        assert!(zxc_path.exists() && zxc_path.is_dir());
+        fs::write(zxc_path.join("config.yml"), "shell: bash\n").unwrap();
 
         let config = Config::new().unwrap();
-        assert_eq!(config.cwd, env::current_dir().unwrap());
-        assert_eq!(config.app_home, zxc_path);
+        assert_eq!(config.settings.shell.value, "bash");
+    }
+
+    #[test]
+    #[serial]
+    fn layered_user_settings() {
+        let temp_home = tempdir().unwrap();
+        let home_path = temp_home.path();
+        set_home(home_path);
+
+        let legacy_path = home_path.join(".zxc");
+        fs::create_dir(&legacy_path).unwrap();
+        fs::write(
+            legacy_path.join("config.yml"),
+            "shell: bash
+confirm: true
+",
+        )
+        .unwrap();
+        let xdg_path = home_path.join(".config").join("zxc");
+        fs::create_dir_all(&xdg_path).unwrap();
+        fs::write(
+            xdg_path.join("config.yml"),
+            "shell: zsh
+",
+        )
+        .unwrap();
+
+        // XDG configuration takes precedence, legacy one is not ignored.
+        let config = Config::new().unwrap();
+        assert_eq!(config.app_home, xdg_path);
+        assert_eq!(config.settings.shell.value, "zsh");
+        assert!(config.settings.confirm.value);
     }
 
     #[test]
@@ -123,10 +314,13 @@ mod config_tests {
     fn missing_home_env() {
         let env_name = home_env_name();
         let original_home = env::var(env_name).unwrap();
+        set_home(Path::new(&original_home));
         unsafe { env::remove_var(env_name) };
 
         let result = Config::new();
-        assert!(result.is_err_and(|e| e.kind() == ErrorKind::NotFound));
+        assert!(result.is_err_and(
+            |e| matches!(e, ZxcError::Io { source, .. } if source.kind() == ErrorKind::NotFound)
+        ));
 
         unsafe { env::set_var(env_name, original_home) };
     }
@@ -147,8 +341,117 @@ mod config_tests {
         fs::remove_dir(cwd_path).unwrap();
 
         let result = Config::new();
-        assert!(result.is_err_and(|e| e.kind() == ErrorKind::NotFound));
+        assert!(result.is_err_and(
+            |e| matches!(e, ZxcError::Io { source, .. } if source.kind() == ErrorKind::NotFound)
+        ));
 
         env::set_current_dir(original_cwd).unwrap();
     }
 }
+
+#[cfg(test)]
+mod set_directory_tests {
+    use super::Config;
+    use crate::error::ZxcError;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    #[test]
+    fn relative_directory() {
+        let dir = tempdir().unwrap();
+        let cwd = dir.path().join("project");
+        let other_dir = dir.path().join("other");
+        fs::create_dir(&cwd).unwrap();
+        fs::create_dir(&other_dir).unwrap();
+        let mut config = Config {
+            cwd,
+            ..Default::default()
+        };
+
+        config.set_directory(Path::new("../other")).unwrap();
+        assert_eq!(config.cwd, other_dir.canonicalize().unwrap());
+
+        config.set_definition_file(Path::new("ci/tasks.yml"));
+        assert_eq!(
+            config.definition_file,
+            Some(config.cwd.join("ci").join("tasks.yml"))
+        );
+    }
+
+    #[test]
+    fn directory_not_found() {
+        let dir = tempdir().unwrap();
+        let mut config = Config {
+            cwd: dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        let result = config.set_directory(Path::new("missing"));
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Discovery { .. })));
+        assert_eq!(config.cwd, dir.path());
+    }
+}
+
+#[cfg(test)]
+mod find_app_home_tests {
+    use super::{find_app_home, home_env_name};
+    use std::ffi::OsString;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    /// Create environment lookup from provided variables.
+    fn env_from(vars: Vec<(&'static str, &Path)>) -> impl Fn(&str) -> Option<OsString> {
+        let vars: Vec<(&str, OsString)> = vars
+            .into_iter()
+            .map(|(name, value)| (name, value.as_os_str().to_os_string()))
+            .collect();
+        move |name| {
+            vars.iter()
+                .find(|(x, _)| *x == name)
+                .map(|(_, value)| value.clone())
+        }
+    }
+
+    #[test]
+    fn zxc_home_override() {
+        let home = tempdir().unwrap();
+        let zxc_home = tempdir().unwrap();
+        fs::create_dir(home.path().join(".zxc")).unwrap();
+
+        let env = env_from(vec![
+            (home_env_name(), home.path()),
+            ("ZXC_HOME", zxc_home.path()),
+        ]);
+        assert_eq!(find_app_home(env).unwrap(), zxc_home.path());
+    }
+
+    #[test]
+    fn xdg_config_home() {
+        let home = tempdir().unwrap();
+        let xdg_config_home = tempdir().unwrap();
+
+        let env = env_from(vec![
+            (home_env_name(), home.path()),
+            ("XDG_CONFIG_HOME", xdg_config_home.path()),
+        ]);
+        assert_eq!(
+            find_app_home(env).unwrap(),
+            xdg_config_home.path().join("zxc")
+        );
+    }
+
+    #[test]
+    fn xdg_preferred_over_legacy() {
+        let home = tempdir().unwrap();
+        fs::create_dir(home.path().join(".zxc")).unwrap();
+        fs::create_dir_all(home.path().join(".config").join("zxc")).unwrap();
+
+        let env = env_from(vec![(home_env_name(), home.path())]);
+        assert_eq!(
+            find_app_home(env).unwrap(),
+            home.path().join(".config").join("zxc")
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::def_file_finder::find_definition_files;
+use crate::def_file_loader::load_defs_from_file;
+use crate::error::ZxcError;
+use crate::yaml_command_defs::{merge_yaml_defs, resolve_extends};
+use crate::zxc_command_defs::{find_template_warnings, load_zxc_def};
+
+/// Result of definition files validation.
+pub struct CheckReport {
+    /// Checked definition files.
+    pub definition_files: Vec<PathBuf>,
+    /// Problems found.
+    pub errors: Vec<ZxcError>,
+    /// Non-fatal problems found.
+    pub warnings: Vec<String>,
+}
+
+/// Validate all discovered definition files.
+/// Unlike regular loading, validation continues after a problem is found,
+/// so all problems are reported at once.
+pub fn check_definitions(config: &Config) -> CheckReport {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    // Find definition files.
+    let found_files = match find_definition_files(config) {
+        Ok(x) => x,
+        Err(e) => {
+            return CheckReport {
+                definition_files: vec![],
+                errors: vec![e],
+                warnings,
+            };
+        }
+    };
+    let definition_files: Vec<PathBuf> = found_files.iter().map(|x| x.path.clone()).collect();
+    if definition_files.is_empty() {
+        errors.push(ZxcError::Discovery {
+            path: config.cwd.clone(),
+            message: "no definition files found".to_string(),
+        });
+    }
+
+    // Load each file separately, so parsing problems are reported for each of them.
+    let mut yaml_command_defs_vec = vec![];
+    for definition_file in found_files {
+        match load_defs_from_file(definition_file.path, definition_file.is_overlay) {
+            Ok(x) => yaml_command_defs_vec.push(x),
+            Err(e) => errors.push(e),
+        }
+    }
+    let yaml_command_defs = match resolve_extends(merge_yaml_defs(yaml_command_defs_vec)) {
+        Ok(x) => x,
+        Err(e) => {
+            errors.push(e);
+            return CheckReport {
+                definition_files,
+                errors,
+                warnings,
+            };
+        }
+    };
+
+    // Validate commands and their templates.
+    // Abstract commands are validated as a part of commands extending them.
+    for (yaml_command_name, yaml_command_def) in yaml_command_defs {
+        if yaml_command_def.is_abstract() {
+            continue;
+        }
+        match load_zxc_def(yaml_command_name, yaml_command_def) {
+            Ok(zxc_command_def) => warnings.extend(find_template_warnings(&zxc_command_def)),
+            Err(e) => errors.extend(e),
+        }
+    }
+
+    CheckReport {
+        definition_files,
+        errors,
+        warnings,
+    }
+}
+
+#[cfg(test)]
+mod check_definitions_tests {
+    use super::check_definitions;
+    use crate::config::Config;
+    use crate::error::ZxcError;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn valid() {
+        let cwd = tempdir().unwrap();
+        let app_home = tempdir().unwrap();
+        let config = Config {
+            cwd: cwd.path().to_path_buf(),
+            app_home: app_home.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        let content = r#"
+          greet:
+            command: echo "Hello {{ name }}!"
+            arguments:
+              name:
+                flags: ["-n", "--name"]
+                default: User
+        "#;
+        fs::write(cwd.path().join(".zxc.yml"), content).unwrap();
+
+        let report = check_definitions(&config);
+        assert_eq!(report.definition_files.len(), 1);
+        assert!(report.errors.is_empty());
+        assert!(report.warnings.is_empty());
+    }
+
+    #[test]
+    fn all_problems_reported() {
+        let cwd = tempdir().unwrap();
+        let app_home = tempdir().unwrap();
+        let config = Config {
+            cwd: cwd.path().to_path_buf(),
+            app_home: app_home.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        let content = r#"
+          greet:
+            command: echo "Hello {{ nmae }}!"
+            arguments:
+              name:
+                flags: ["-n", "--name"]
+                default: User
+          unused:
+            command: echo
+            arguments:
+              value:
+                flags: ["--value"]
+                default: ""
+          broken:
+            command: echo {{ value
+          invalid:
+            command: echo
+            arguments:
+              value:
+                flags: []
+        "#;
+        fs::write(cwd.path().join(".zxc.yml"), content).unwrap();
+
+        let report = check_definitions(&config);
+        assert_eq!(report.errors.len(), 3);
+        assert_eq!(report.warnings.len(), 1);
+        assert!(
+            report
+                .errors
+                .iter()
+                .any(|e| matches!(e, ZxcError::Validation { command, .. } if command == "invalid"))
+        );
+    }
+
+    #[test]
+    fn no_files() {
+        let cwd = tempdir().unwrap();
+        let app_home = tempdir().unwrap();
+        let config = Config {
+            cwd: cwd.path().to_path_buf(),
+            app_home: app_home.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        let report = check_definitions(&config);
+        assert_eq!(report.errors.len(), 1);
+    }
+}
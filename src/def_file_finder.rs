@@ -1,24 +1,68 @@
 use crate::config::Config;
+use crate::error::ZxcError;
+use crate::external_mapping::find_mapped_def_files;
+use crate::virtual_defs::{find_virtual_def_files, load_directory_config};
 use std::env::consts::OS;
-use std::io::{Error, ErrorKind};
 use std::path::{Path, PathBuf};
 
+/// Allowed names of definition file.
+const ALLOWED_NAMES: [&str; 8] = [
+    ".zxc.yml",
+    ".zxc.yaml",
+    ".zxc.toml",
+    ".zxc.json",
+    "zxc.yml",
+    "zxc.yaml",
+    "zxc.toml",
+    "zxc.json",
+];
+
+/// Found definition file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DefinitionFile {
+    /// Path to definition file.
+    pub path: PathBuf,
+    /// Commands of overlay file patch commands with lower precedence instead of replacing them.
+    pub is_overlay: bool,
+}
+
+impl DefinitionFile {
+    fn new(path: PathBuf, is_overlay: bool) -> Self {
+        DefinitionFile { path, is_overlay }
+    }
+}
+
+/// Allowed names of global definition file.
+pub const GLOBAL_NAMES: [&str; 4] = ["global.yml", "global.yaml", "global.toml", "global.json"];
+
 /// Find definition file in specified directory.
-fn find_def_file(directory_path: &Path) -> Result<Option<PathBuf>, Error> {
+fn find_def_file(directory_path: &Path) -> Result<Option<PathBuf>, ZxcError> {
+    find_file_by_names(directory_path, &ALLOWED_NAMES)
+}
+
+/// Find one of files with allowed names in specified directory.
+fn find_file_by_names(
+    directory_path: &Path,
+    allowed_names: &[&str],
+) -> Result<Option<PathBuf>, ZxcError> {
+    let discovery_error = |message: String| ZxcError::Discovery {
+        path: directory_path.to_path_buf(),
+        message,
+    };
+
     // Check path exists.
     if !directory_path.exists() {
-        return Err(Error::from(ErrorKind::NotFound));
+        return Err(discovery_error("directory not found".to_string()));
     }
 
     // Check path is a directory.
     if !directory_path.is_dir() {
-        return Err(Error::from(ErrorKind::NotADirectory));
+        return Err(discovery_error("not a directory".to_string()));
     }
 
     // Iterate through allowed file names.
-    const ALLOWED_NAMES: [&str; 4] = [".zxc.yml", ".zxc.yaml", "zxc.yml", "zxc.yaml"];
     let mut found_files = vec![];
-    for name in ALLOWED_NAMES {
+    for name in allowed_names {
         let file_path = directory_path.join(name);
         if file_path.exists() {
             found_files.push(file_path);
@@ -27,7 +71,15 @@ fn find_def_file(directory_path: &Path) -> Result<Option<PathBuf>, Error> {
 
     // Disallow multiple found.
     if found_files.len() > 1 {
-        return Err(Error::other("Multiple definition files found"));
+        let found_names: Vec<String> = found_files
+            .iter()
+            .filter_map(|x| x.file_name())
+            .map(|x| x.to_string_lossy().to_string())
+            .collect();
+        return Err(discovery_error(format!(
+            "multiple definition files found: {}",
+            found_names.join(", ")
+        )));
     }
 
     // Check if any found.
@@ -39,7 +91,7 @@ fn find_def_file(directory_path: &Path) -> Result<Option<PathBuf>, Error> {
     Ok(Some(found_files[0].clone()))
 }
 
-/// Returns path to `$<HOME>/.zxc/<mirrored CWD path>`.
+/// Returns path to `<app home>/<mirrored CWD path>`.
 fn get_external_dir(config: &Config) -> PathBuf {
     let mut app_home = config.app_home.clone();
     let cwd = &config.cwd;
@@ -64,44 +116,121 @@ fn get_external_dir(config: &Config) -> PathBuf {
 
 /// Find definition files.
 /// - local - from CWD.
-/// - external - from `$<HOME>/.zxc/<mirrored CWD path>`.
-///   E.g., if CWD is `/opt/app/` then `$<HOME>/.zxc/opt/app/` should be used.
+/// - external - from `<app home>/<mirrored CWD path>`.
+///   E.g., if CWD is `/opt/app/` then `$XDG_CONFIG_HOME/zxc/opt/app/` should be used.
 ///
 /// Following file names are allowed:
 /// - `.zxc.yml`
 /// - `.zxc.yaml`
+/// - `.zxc.toml`
+/// - `.zxc.json`
 /// - `zxc.yml`
 /// - `zxc.yaml`
+/// - `zxc.toml`
+/// - `zxc.json`
+///
+/// It's not allowed to have multiple definition files in one directory, regardless of format.
+///
+/// Global definition file (`<app home>/global.yml`, `.yaml`, `.toml` or `.json`) is always
+/// included first - it has lowest precedence.
+///
+/// Definition files from additional search directories are included before local one.
+/// If parent walking is enabled and no local definition file is found in CWD,
+/// parent directories are searched.
+///
+/// Files of virtual definition sources (`package.json`, `Makefile`, `.cargo/config.toml`) from CWD
+/// are included first if enabled by directory configuration - local or external.
 ///
-/// It's not allowed to have multiple definition files in one directory.
+/// Definition files mapped to CWD by `<app home>/external.yml` (by path glob, git remote
+/// or repository name) are included last.
+///
+/// External and mapped definition files are overlays - they patch commands instead of replacing them.
+///
+/// Explicitly selected definition file bypasses discovery - only this file is returned.
 ///
 /// Returns empty container if nothing is found.
-pub fn find_definition_files(config: &Config) -> Vec<PathBuf> {
-    let mut found_files: Vec<PathBuf> = vec![];
+pub fn find_definition_files(config: &Config) -> Result<Vec<DefinitionFile>, ZxcError> {
+    // Use explicitly selected definition file.
+    if let Some(definition_file) = &config.definition_file {
+        if !definition_file.is_file() {
+            return Err(ZxcError::Discovery {
+                path: definition_file.clone(),
+                message: "definition file not found".to_string(),
+            });
+        }
+        return Ok(vec![DefinitionFile::new(definition_file.clone(), false)]);
+    }
+
+    let mut found_files: Vec<DefinitionFile> = vec![];
+
+    // Get global definition file - it has lowest precedence.
+    if config.app_home.is_dir() {
+        let global_def_file = find_file_by_names(&config.app_home, &GLOBAL_NAMES)?;
+        if let Some(path) = global_def_file {
+            found_files.push(DefinitionFile::new(path, false));
+        }
+    }
+
+    // Get virtual definition files.
+    let external_dir = get_external_dir(config);
+    let mut virtual_sources = load_directory_config(&config.cwd)?.virtual_definitions;
+    if external_dir.is_dir() {
+        virtual_sources.extend(load_directory_config(&external_dir)?.virtual_definitions);
+    }
+    virtual_sources.sort();
+    virtual_sources.dedup();
+    let virtual_def_files = find_virtual_def_files(&config.cwd, &virtual_sources);
+    found_files.extend(
+        virtual_def_files
+            .into_iter()
+            .map(|x| DefinitionFile::new(x, false)),
+    );
+
+    // Get definition files from additional search directories.
+    for search_dir in &config.settings.extra_search_dirs.value {
+        if let Some(path) = find_def_file(search_dir)? {
+            found_files.push(DefinitionFile::new(path, false));
+        }
+    }
 
     // Get local definition file.
-    let local_def_file = find_def_file(&config.cwd).expect("Failed to find local definition file");
+    // If enabled, parent directories are searched until definition file is found.
+    let mut local_def_file = find_def_file(&config.cwd)?;
+    if config.settings.parent_walk.value {
+        for parent_dir in config.cwd.ancestors().skip(1) {
+            if local_def_file.is_some() {
+                break;
+            }
+            local_def_file = find_def_file(parent_dir)?;
+        }
+    }
     if let Some(path) = local_def_file {
-        found_files.push(path);
+        found_files.push(DefinitionFile::new(path, false));
     }
 
-    // Get external dir path.
-    let external_dir = get_external_dir(config);
+    // Get external definition file.
     if external_dir.exists() && external_dir.is_dir() {
-        let external_def_file =
-            find_def_file(&external_dir).expect("Failed to find external definition file");
+        let external_def_file = find_def_file(&external_dir)?;
         if let Some(path) = external_def_file {
-            found_files.push(path);
+            found_files.push(DefinitionFile::new(path, true));
         }
     }
-    found_files
+
+    // Get definition files mapped by external mapping file - they have highest precedence.
+    let mapped_def_files = find_mapped_def_files(config)?;
+    found_files.extend(
+        mapped_def_files
+            .into_iter()
+            .map(|x| DefinitionFile::new(x, true)),
+    );
+    Ok(found_files)
 }
 
 #[cfg(test)]
 mod find_def_file_tests {
     use super::find_def_file;
+    use crate::error::ZxcError;
     use std::fs;
-    use std::io::ErrorKind;
     use std::path::Path;
     use tempfile::tempdir;
 
@@ -133,16 +262,43 @@ mod find_def_file_tests {
         fs::File::create(&file2).unwrap();
 
         let result = find_def_file(dir.path());
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err().kind(), ErrorKind::Other);
+        assert!(result.is_err_and(|e| match e {
+            ZxcError::Discovery { path, message } => {
+                path == dir.path() && message.contains(".zxc.yml") && message.contains("zxc.yaml")
+            }
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn multiple_formats_found() {
+        let dir = tempdir().unwrap();
+        fs::File::create(dir.path().join("zxc.yml")).unwrap();
+        fs::File::create(dir.path().join(".zxc.toml")).unwrap();
+
+        let result = find_def_file(dir.path());
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Discovery { .. })));
+    }
+
+    #[test]
+    fn toml_file_found() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("zxc.toml");
+        fs::File::create(&file_path).unwrap();
+
+        let result = find_def_file(dir.path());
+        assert!(result.is_ok_and(|x| x == Some(file_path)));
     }
 
     #[test]
     fn invalid_path() {
         let invalid_path = Path::new("/invalid/path");
         let result = find_def_file(invalid_path);
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err().kind(), ErrorKind::NotFound);
+        assert!(
+            result.is_err_and(
+                |e| matches!(e, ZxcError::Discovery { path, .. } if path == invalid_path)
+            )
+        );
     }
 }
 
@@ -156,7 +312,11 @@ mod get_external_dir_tests {
     fn ok() {
         let cwd = PathBuf::from("/opt/app");
         let app_home = PathBuf::from("/home/user/.zxc");
-        let config = Config { cwd, app_home };
+        let config = Config {
+            cwd,
+            app_home,
+            ..Default::default()
+        };
 
         let expected_path = PathBuf::from("/home/user/.zxc/opt/app");
         let result = get_external_dir(&config);
@@ -166,12 +326,20 @@ mod get_external_dir_tests {
 
 #[cfg(test)]
 mod find_definition_files_tests {
-    use super::{find_definition_files, get_external_dir};
+    use super::{DefinitionFile, find_definition_files, get_external_dir};
     use crate::Config;
+    use crate::error::ZxcError;
+    use crate::virtual_defs::DIRECTORY_CONFIG_NAME;
     use std::fs;
     use std::io::Write;
+    use std::path::PathBuf;
     use tempfile::tempdir;
 
+    /// Paths of found definition files.
+    fn paths(definition_files: Vec<DefinitionFile>) -> Vec<PathBuf> {
+        definition_files.into_iter().map(|x| x.path).collect()
+    }
+
     #[test]
     fn local_and_external() {
         let dir = tempdir().unwrap();
@@ -180,6 +348,7 @@ mod find_definition_files_tests {
         let config = Config {
             cwd: cwd.to_path_buf(),
             app_home: app_home.path().to_path_buf(),
+            ..Default::default()
         };
 
         // Create a local definition file
@@ -194,7 +363,7 @@ mod find_definition_files_tests {
         let mut external_file = fs::File::create(&external_def).unwrap();
         writeln!(external_file, "external file content").unwrap();
 
-        let result = find_definition_files(&config);
+        let result = paths(find_definition_files(&config).unwrap());
         assert_eq!(result.len(), 2);
         assert!(result.contains(&local_def));
         assert!(result.contains(&external_def));
@@ -205,10 +374,176 @@ mod find_definition_files_tests {
         let dir = tempdir().unwrap();
         let cwd = dir.path().to_path_buf();
         let app_home = tempdir().unwrap().path().to_path_buf();
-        let config = Config { cwd, app_home };
+        let config = Config {
+            cwd,
+            app_home,
+            ..Default::default()
+        };
+
+        let result = paths(find_definition_files(&config).unwrap());
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn global_file() {
+        let dir = tempdir().unwrap();
+        let cwd = dir.path();
+        let app_home = tempdir().unwrap();
+        let config = Config {
+            cwd: cwd.to_path_buf(),
+            app_home: app_home.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        let local_def = cwd.join(".zxc.yml");
+        let global_def = app_home.path().join("global.yml");
+        fs::write(&local_def, "").unwrap();
+        fs::write(&global_def, "").unwrap();
+
+        let result = paths(find_definition_files(&config).unwrap());
+        assert_eq!(result, vec![global_def, local_def]);
+    }
+
+    #[test]
+    fn external_mapping() {
+        let dir = tempdir().unwrap();
+        let cwd = dir.path();
+        let app_home = tempdir().unwrap();
+        let config = Config {
+            cwd: cwd.to_path_buf(),
+            app_home: app_home.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        let local_def = cwd.join(".zxc.yml");
+        let mapped_def = app_home.path().join("overlay.yml");
+        let mapping = format!("- path: {}\n  definitions: [overlay.yml]\n", cwd.display());
+        fs::write(&local_def, "").unwrap();
+        fs::write(&mapped_def, "").unwrap();
+        fs::write(app_home.path().join("external.yml"), mapping).unwrap();
+
+        let result = paths(find_definition_files(&config).unwrap());
+        assert_eq!(result, vec![local_def, mapped_def]);
+    }
+
+    #[test]
+    fn explicit_file() {
+        let dir = tempdir().unwrap();
+        let cwd = dir.path();
+        let app_home = tempdir().unwrap();
+        let explicit_def = cwd.join("ci").join("tasks.yml");
+        let config = Config {
+            cwd: cwd.to_path_buf(),
+            app_home: app_home.path().to_path_buf(),
+            definition_file: Some(explicit_def.clone()),
+            ..Default::default()
+        };
+
+        // Missing file is an error.
+        let result = find_definition_files(&config);
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Discovery { .. })));
+
+        // Discovered files are ignored.
+        fs::create_dir(cwd.join("ci")).unwrap();
+        fs::write(&explicit_def, "").unwrap();
+        fs::write(cwd.join(".zxc.yml"), "").unwrap();
+        fs::write(app_home.path().join("global.yml"), "").unwrap();
+
+        let result = paths(find_definition_files(&config).unwrap());
+        assert_eq!(result, vec![explicit_def]);
+    }
+
+    #[test]
+    fn multiple_global_files() {
+        let dir = tempdir().unwrap();
+        let app_home = tempdir().unwrap();
+        let config = Config {
+            cwd: dir.path().to_path_buf(),
+            app_home: app_home.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        fs::write(app_home.path().join("global.yml"), "").unwrap();
+        fs::write(app_home.path().join("global.toml"), "").unwrap();
 
         let result = find_definition_files(&config);
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Discovery { .. })));
+    }
+
+    #[test]
+    fn parent_walk() {
+        let dir = tempdir().unwrap();
+        let cwd = dir.path().join("nested").join("dir");
+        fs::create_dir_all(&cwd).unwrap();
+        let app_home = tempdir().unwrap();
+        let mut config = Config {
+            cwd: cwd.clone(),
+            app_home: app_home.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        let parent_def = dir.path().join(".zxc.yml");
+        fs::write(&parent_def, "").unwrap();
+
+        let result = paths(find_definition_files(&config).unwrap());
         assert!(result.is_empty());
+
+        config.settings.parent_walk.value = true;
+        let result = paths(find_definition_files(&config).unwrap());
+        assert_eq!(result, vec![parent_def]);
+    }
+
+    #[test]
+    fn extra_search_dirs() {
+        let dir = tempdir().unwrap();
+        let extra_dir = tempdir().unwrap();
+        let app_home = tempdir().unwrap();
+        let mut config = Config {
+            cwd: dir.path().to_path_buf(),
+            app_home: app_home.path().to_path_buf(),
+            ..Default::default()
+        };
+        config.settings.extra_search_dirs.value = vec![extra_dir.path().to_path_buf()];
+
+        let local_def = dir.path().join(".zxc.yml");
+        let extra_def = extra_dir.path().join("zxc.toml");
+        fs::write(&local_def, "").unwrap();
+        fs::write(&extra_def, "").unwrap();
+
+        let result = paths(find_definition_files(&config).unwrap());
+        assert_eq!(result, vec![extra_def, local_def]);
+    }
+
+    #[test]
+    fn virtual_definitions() {
+        let dir = tempdir().unwrap();
+        let cwd = dir.path();
+        let app_home = tempdir().unwrap();
+        let config = Config {
+            cwd: cwd.to_path_buf(),
+            app_home: app_home.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        fs::write(cwd.join("package.json"), "{}").unwrap();
+        fs::write(cwd.join("Makefile"), "").unwrap();
+        fs::write(cwd.join(".zxc.yml"), "").unwrap();
+
+        // Virtual definitions are disabled by default.
+        let result = paths(find_definition_files(&config).unwrap());
+        assert_eq!(result, vec![cwd.join(".zxc.yml")]);
+
+        // Enable in external directory configuration.
+        let external_dir = get_external_dir(&config);
+        fs::create_dir_all(&external_dir).unwrap();
+        fs::write(
+            external_dir.join(DIRECTORY_CONFIG_NAME),
+            "virtual_definitions: [npm]",
+        )
+        .unwrap();
+
+        let result = paths(find_definition_files(&config).unwrap());
+        assert_eq!(result, vec![cwd.join("package.json"), cwd.join(".zxc.yml")]);
     }
 
     #[test]
@@ -219,13 +554,14 @@ mod find_definition_files_tests {
         let config = Config {
             cwd: cwd.to_path_buf(),
             app_home,
+            ..Default::default()
         };
 
         // Create multiple local definition files
         fs::File::create(cwd.join(".zxc.yml")).unwrap();
         fs::File::create(cwd.join("zxc.yaml")).unwrap();
 
-        let result = std::panic::catch_unwind(|| find_definition_files(&config));
-        assert!(result.is_err());
+        let result = find_definition_files(&config);
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Discovery { .. })));
     }
 }
@@ -0,0 +1,291 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::def_file_finder::GLOBAL_NAMES;
+use crate::error::ZxcError;
+use crate::virtual_defs::{VirtualDefSource, load_virtual_defs_from_file};
+use crate::yaml_command_defs::{
+    DefinitionOrigin, DefinitionScope, YamlCommandDefs, YamlDefinitionFile, from_definition_file,
+    load_yaml_defs_from_file, load_yaml_defs_from_str, set_definition_origins,
+};
+
+/// Definition file format.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DefFileFormat {
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl DefFileFormat {
+    /// Determine format based on file extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?;
+        match extension {
+            "yml" | "yaml" => Some(DefFileFormat::Yaml),
+            "toml" => Some(DefFileFormat::Toml),
+            "json" => Some(DefFileFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Load command definitions from a string in specified format.
+pub fn load_defs_from_str(
+    definition_file_content: &str,
+    format: DefFileFormat,
+) -> Result<YamlCommandDefs, ZxcError> {
+    match format {
+        DefFileFormat::Yaml => load_yaml_defs_from_str(definition_file_content),
+        DefFileFormat::Toml => {
+            match toml::from_str::<YamlDefinitionFile>(definition_file_content) {
+                Ok(commands) => Ok(from_definition_file(commands)),
+                Err(e) => Err(ZxcError::from_toml_error(definition_file_content, e)),
+            }
+        }
+        DefFileFormat::Json => match serde_json::from_str(definition_file_content) {
+            Ok(commands) => Ok(from_definition_file(commands)),
+            Err(e) => Err(ZxcError::from_json_error(definition_file_content, e)),
+        },
+    }
+}
+
+/// Load command definitions from a file.
+/// Format is determined based on file extension.
+/// Files of virtual definition sources are recognized by name.
+/// Commands from global definition file are marked as global.
+/// Commands from overlay definition file are marked to patch ones with lower precedence.
+/// Location of each command is recorded.
+pub fn load_defs_from_file(
+    definition_file_path: PathBuf,
+    is_overlay: bool,
+) -> Result<YamlCommandDefs, ZxcError> {
+    if let Some(source) = VirtualDefSource::from_path(&definition_file_path) {
+        let mut defs = load_virtual_defs_from_file(definition_file_path.clone(), source)?;
+        for def in defs.values_mut() {
+            def.origin = Some(DefinitionOrigin {
+                path: definition_file_path.clone(),
+                line: None,
+            });
+        }
+        return Ok(defs);
+    }
+
+    let is_global = definition_file_path
+        .file_name()
+        .and_then(|x| x.to_str())
+        .is_some_and(|x| GLOBAL_NAMES.contains(&x));
+    let mut defs = load_defs_by_format(definition_file_path)?;
+    for def in defs.values_mut() {
+        if is_global {
+            def.scope = DefinitionScope::Global;
+        }
+        def.overlay = is_overlay;
+    }
+    Ok(defs)
+}
+
+/// Load command definitions from a file, based on file extension.
+fn load_defs_by_format(definition_file_path: PathBuf) -> Result<YamlCommandDefs, ZxcError> {
+    let format = match DefFileFormat::from_path(&definition_file_path) {
+        Some(x) => x,
+        None => {
+            return Err(ZxcError::Parse {
+                path: Some(definition_file_path),
+                message: "unknown definition file format".to_string(),
+                snippet: None,
+            });
+        }
+    };
+    if format == DefFileFormat::Yaml {
+        return load_yaml_defs_from_file(definition_file_path);
+    }
+
+    let definition_file_content = match fs::read_to_string(&definition_file_path) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(ZxcError::Io {
+                path: Some(definition_file_path),
+                source: e,
+            });
+        }
+    };
+    let mut defs = load_defs_from_str(&definition_file_content, format)
+        .map_err(|e| e.with_path(&definition_file_path))?;
+    set_definition_origins(&mut defs, &definition_file_path, &definition_file_content);
+    Ok(defs)
+}
+
+#[cfg(test)]
+mod def_file_format_tests {
+    use super::DefFileFormat;
+    use std::path::Path;
+
+    #[test]
+    fn known_extensions() {
+        let cases = [
+            (".zxc.yml", DefFileFormat::Yaml),
+            ("zxc.yaml", DefFileFormat::Yaml),
+            (".zxc.toml", DefFileFormat::Toml),
+            ("zxc.json", DefFileFormat::Json),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(DefFileFormat::from_path(Path::new(name)), Some(expected));
+        }
+    }
+
+    #[test]
+    fn unknown_extension() {
+        assert_eq!(DefFileFormat::from_path(Path::new("zxc.txt")), None);
+        assert_eq!(DefFileFormat::from_path(Path::new("zxc")), None);
+    }
+}
+
+#[cfg(test)]
+mod load_defs_from_str_tests {
+    use super::{DefFileFormat, load_defs_from_str};
+    use crate::error::ZxcError;
+
+    #[test]
+    fn valid_toml() {
+        let toml_content = r#"
+            [greet]
+            command = "echo Hello {{ name }}!"
+            description = "Greets specified person."
+
+            [greet.arguments.name]
+            flags = ["-n", "--name"]
+            default = "User"
+        "#;
+
+        let result = load_defs_from_str(toml_content, DefFileFormat::Toml);
+        assert!(result.is_ok());
+
+        let defs = result.unwrap();
+        let greet_def = defs.get("greet").unwrap();
+        assert_eq!(greet_def.command, "echo Hello {{ name }}!");
+        assert!(greet_def.arguments.as_ref().unwrap().contains_key("name"));
+    }
+
+    #[test]
+    fn valid_json() {
+        let json_content = r#"
+            {
+              "greet": {
+                "command": "echo Hello {{ name }}!",
+                "arguments": {
+                  "name": { "flags": ["-n", "--name"], "default": "User" }
+                }
+              }
+            }
+        "#;
+
+        let result = load_defs_from_str(json_content, DefFileFormat::Json);
+        assert!(result.is_ok());
+
+        let defs = result.unwrap();
+        assert_eq!(defs.get("greet").unwrap().command, "echo Hello {{ name }}!");
+    }
+
+    #[test]
+    fn unknown_field() {
+        let toml_content = r#"
+            [greet]
+            command = "echo Hello"
+            some_unknown_field = "asdf"
+        "#;
+        let result = load_defs_from_str(toml_content, DefFileFormat::Toml);
+        assert!(result.is_err_and(|e| matches!(
+            e,
+            ZxcError::Parse {
+                snippet: Some(_),
+                ..
+            }
+        )));
+
+        let json_content = r#"{ "greet": { "command": "echo Hello", "some_unknown_field": 1 } }"#;
+        let result = load_defs_from_str(json_content, DefFileFormat::Json);
+        assert!(result.is_err_and(|e| matches!(
+            e,
+            ZxcError::Parse {
+                snippet: Some(_),
+                ..
+            }
+        )));
+    }
+}
+
+#[cfg(test)]
+mod load_defs_from_file_tests {
+    use super::load_defs_from_file;
+    use crate::error::ZxcError;
+    use crate::yaml_command_defs::DefinitionScope;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn valid_file() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("zxc.toml");
+        fs::write(&file_path, "[run]\ncommand = \"cargo run\"\n").unwrap();
+
+        let result = load_defs_from_file(file_path, false);
+        assert!(result.is_ok_and(|x| x.get("run").unwrap().command == "cargo run"));
+    }
+
+    #[test]
+    fn invalid_file() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("zxc.json");
+        fs::write(&file_path, "{ \"run\": }").unwrap();
+
+        let result = load_defs_from_file(file_path.clone(), false);
+        assert!(
+            result.is_err_and(
+                |e| matches!(e, ZxcError::Parse { path, .. } if path == Some(file_path))
+            )
+        );
+    }
+
+    #[test]
+    fn global_file() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("global.yml");
+        fs::write(&file_path, "run:\n  command: cargo run\n").unwrap();
+
+        let result = load_defs_from_file(file_path, false);
+        assert!(result.is_ok_and(|x| x.get("run").unwrap().scope == DefinitionScope::Global));
+    }
+
+    #[test]
+    fn overlay_file() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("zxc.yml");
+        fs::write(&file_path, "run:\n  command: cargo run\n").unwrap();
+
+        let result = load_defs_from_file(file_path.clone(), false);
+        assert!(result.is_ok_and(|x| !x.get("run").unwrap().overlay));
+        let result = load_defs_from_file(file_path, true);
+        assert!(result.is_ok_and(|x| x.get("run").unwrap().overlay));
+    }
+
+    #[test]
+    fn virtual_file() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("package.json");
+        fs::write(&file_path, "{ \"scripts\": { \"build\": \"tsc\" } }").unwrap();
+
+        let result = load_defs_from_file(file_path, false);
+        assert!(result.is_ok_and(|x| x.get("npm:build").unwrap().command == "npm run build"));
+    }
+
+    #[test]
+    fn unknown_format() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("zxc.txt");
+        fs::write(&file_path, "").unwrap();
+
+        let result = load_defs_from_file(file_path, false);
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Parse { .. })));
+    }
+}
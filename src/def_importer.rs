@@ -0,0 +1,1423 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::ZxcError;
+use crate::yaml_command_defs::{
+    YamlArgumentDef, YamlCommandDef, YamlCommandDefs, YamlDefaultValue,
+};
+
+/// Format of a file commands are imported from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImportFormat {
+    Makefile,
+    Justfile,
+    PackageJson,
+    Taskfile,
+}
+
+impl ImportFormat {
+    /// Determine format based on file name.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let file_name = path.file_name()?.to_str()?;
+        match file_name {
+            "Makefile" | "makefile" | "GNUmakefile" => Some(ImportFormat::Makefile),
+            x if x.ends_with(".mk") => Some(ImportFormat::Makefile),
+            "justfile" | "Justfile" | ".justfile" => Some(ImportFormat::Justfile),
+            "package.json" => Some(ImportFormat::PackageJson),
+            "Taskfile.yml" | "Taskfile.yaml" | "taskfile.yml" | "taskfile.yaml" => {
+                Some(ImportFormat::Taskfile)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Result of an import.
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    /// Imported command definitions.
+    pub defs: YamlCommandDefs,
+    /// Constructs which couldn't be translated.
+    pub warnings: Vec<String>,
+}
+
+impl ImportReport {
+    fn warn(&mut self, line: Option<usize>, message: String) {
+        let warning = match line {
+            Some(x) => format!("line {x}: {message}"),
+            None => message,
+        };
+        self.warnings.push(warning);
+    }
+}
+
+/// Change variable name to argument name - lowercase, only alphanumeric characters and `_`.
+fn argument_name(variable_name: &str) -> String {
+    variable_name
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_lowercase(),
+            false => '_',
+        })
+        .collect()
+}
+
+/// Create long flag for an argument.
+/// Flags reserved by `zxc` are prefixed to avoid collisions.
+fn long_flag(argument_name: &str) -> String {
+    let flag = argument_name.replace('_', "-");
+    match flag.as_str() {
+        "help" | "version" => format!("--arg-{flag}"),
+        _ => format!("--{flag}"),
+    }
+}
+
+/// Keep untranslated template expression as a literal text.
+fn raw_template(expression: &str) -> String {
+    format!("{{% raw %}}{expression}{{% endraw %}}")
+}
+
+/// Create named argument definition for a variable.
+fn variable_argument_def(variable_name: &str, default: Option<String>) -> YamlArgumentDef {
+    YamlArgumentDef {
+        flags: vec![long_flag(&argument_name(variable_name))],
+        default: default.map(YamlDefaultValue::Value),
+        description: Some(format!("Value of `{variable_name}`.")),
+        ..Default::default()
+    }
+}
+
+/// Create command definition from command lines.
+fn command_def(
+    lines: Vec<String>,
+    description: Option<String>,
+    arguments: BTreeMap<String, YamlArgumentDef>,
+) -> YamlCommandDef {
+    // Commands are run until first failure, same as in recipes.
+    let mut command = String::new();
+    for line in lines {
+        if !command.is_empty() {
+            match command.ends_with('\\') {
+                true => command.push('\n'),
+                false => command.push_str(" &&\n"),
+            }
+        }
+        command.push_str(&line);
+    }
+
+    YamlCommandDef {
+        command,
+        description,
+        arguments: match arguments.is_empty() {
+            true => None,
+            false => Some(arguments),
+        },
+        ..Default::default()
+    }
+}
+
+/// Join description comments into a single description.
+fn comments_description(comments: &[String]) -> Option<String> {
+    match comments.is_empty() {
+        true => None,
+        false => Some(comments.join(" ")),
+    }
+}
+
+/// Translate recipe line prefixes controlling echo and error handling (`@`, `-`, `+`).
+fn translate_recipe_prefixes(line: &str) -> String {
+    let stripped = line.trim_start_matches(['@', '-', '+']);
+    let ignore_errors = line[..line.len() - stripped.len()].contains('-');
+    let stripped = stripped.trim_start();
+    match ignore_errors && !stripped.ends_with('\\') {
+        true => format!("{stripped} || true"),
+        false => stripped.to_string(),
+    }
+}
+
+/// Makefile rule.
+struct MakeRule {
+    line: usize,
+    targets: Vec<String>,
+    prerequisites: Vec<String>,
+    description: Option<String>,
+    recipe: Vec<(usize, String)>,
+}
+
+/// Directives which are not translated.
+const MAKE_DIRECTIVES: [&str; 14] = [
+    "include", "-include", "sinclude", "ifeq", "ifneq", "ifdef", "ifndef", "else", "endif",
+    "define", "export", "unexport", "override", "vpath",
+];
+
+/// Split Makefile line into variable assignment parts, if it's an assignment.
+fn split_make_assignment(line: &str) -> Option<(&str, &str, &str)> {
+    const OPERATORS: [&str; 6] = ["::=", ":=", "?=", "+=", "!=", "="];
+    let equals_position = line.find('=')?;
+    if let Some(colon_position) = line.find(':') {
+        // Rule, unless colon is a part of an operator.
+        let is_operator =
+            line[colon_position..].starts_with(":=") || line[colon_position..].starts_with("::=");
+        if colon_position < equals_position && !is_operator {
+            return None;
+        }
+    }
+
+    for operator in OPERATORS {
+        if let Some(position) = line.find(operator) {
+            let name = line[..position].trim();
+            let value = line[position + operator.len()..].trim();
+            let is_name_valid = !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+            if is_name_valid {
+                return Some((name, operator, value));
+            }
+            return None;
+        }
+    }
+    None
+}
+
+/// Append literal text of a recipe line, keeping template syntax in it untranslated.
+fn push_make_literal(output: &mut String, literal: &mut String) {
+    if ["{{", "{%", "{#"].iter().any(|x| literal.contains(x)) {
+        output.push_str(&raw_template(literal));
+    } else {
+        output.push_str(literal);
+    }
+    literal.clear();
+}
+
+/// Translate Makefile references in a recipe line.
+/// Known variables are replaced with template placeholders and collected into `used_variables`.
+/// Other text is kept literally.
+fn translate_make_line(
+    line: &str,
+    target: &str,
+    variables: &BTreeMap<String, String>,
+    used_variables: &mut BTreeSet<String>,
+) -> (String, Vec<String>) {
+    let mut output = String::new();
+    let mut literal = String::new();
+    let mut untranslated = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            literal.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            // Escaped dollar sign.
+            Some('$') => literal.push('$'),
+            // Target name.
+            Some('@') => literal.push_str(target),
+            // Variable reference or function call.
+            Some(open @ ('(' | '{')) => {
+                let close = if open == '(' { ')' } else { '}' };
+                let mut reference = String::new();
+                let mut depth = 1;
+                for c in chars.by_ref() {
+                    if c == open {
+                        depth += 1;
+                    } else if c == close {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    reference.push(c);
+                }
+
+                if variables.contains_key(&reference) {
+                    push_make_literal(&mut output, &mut literal);
+                    output.push_str(&format!("{{{{ {} }}}}", argument_name(&reference)));
+                    used_variables.insert(reference);
+                } else {
+                    let original = format!("${open}{reference}{close}");
+                    literal.push_str(&original);
+                    untranslated.push(original);
+                }
+            }
+            // Other automatic variables.
+            Some(x) => {
+                let original = format!("${x}");
+                literal.push_str(&original);
+                untranslated.push(original);
+            }
+            None => literal.push('$'),
+        }
+    }
+    push_make_literal(&mut output, &mut literal);
+    (output, untranslated)
+}
+
+/// Import commands from a Makefile.
+/// Each target becomes a command, variables used by recipes become arguments.
+pub fn import_makefile(content: &str) -> ImportReport {
+    let mut report = ImportReport::default();
+    let mut variables: BTreeMap<String, String> = BTreeMap::new();
+    let mut rules: Vec<MakeRule> = Vec::new();
+    let mut comments: Vec<String> = Vec::new();
+    let mut in_define = false;
+
+    let lines: Vec<&str> = content.lines().collect();
+    let mut index = 0;
+    while index < lines.len() {
+        let line_number = index + 1;
+        let mut line = lines[index].to_string();
+        index += 1;
+
+        // Recipe lines - continuation lines are kept as they are.
+        if let Some(recipe_line) = line.strip_prefix('\t') {
+            match rules.last_mut() {
+                Some(rule) => rule.recipe.push((line_number, recipe_line.to_string())),
+                None => report.warn(
+                    Some(line_number),
+                    "recipe line outside of a rule ignored".to_string(),
+                ),
+            }
+            continue;
+        }
+
+        // Join continuation lines.
+        while line.ends_with('\\') && index < lines.len() {
+            line.pop();
+            line.push(' ');
+            line.push_str(lines[index].trim());
+            index += 1;
+        }
+        let trimmed = line.trim();
+
+        // Skip multi-line variable definitions.
+        if in_define {
+            if trimmed == "endef" {
+                in_define = false;
+            }
+            continue;
+        }
+
+        // Empty lines separate comments from rules.
+        if trimmed.is_empty() {
+            comments.clear();
+            continue;
+        }
+
+        // Comments are used as descriptions of following rule.
+        if let Some(comment) = trimmed.strip_prefix('#') {
+            let comment = comment.trim_start_matches('#').trim();
+            if !comment.is_empty() {
+                comments.push(comment.to_string());
+            }
+            continue;
+        }
+
+        // Directives.
+        let first_word = trimmed.split_whitespace().next().unwrap_or_default();
+        if MAKE_DIRECTIVES.contains(&first_word) {
+            if first_word == "define" {
+                in_define = true;
+            }
+            report.warn(
+                Some(line_number),
+                format!("directive '{first_word}' not translated"),
+            );
+            comments.clear();
+            continue;
+        }
+
+        // Variable assignments.
+        if let Some((name, operator, value)) = split_make_assignment(trimmed) {
+            match operator {
+                "!=" | "+=" => report.warn(
+                    Some(line_number),
+                    format!("assignment '{name} {operator}' not translated"),
+                ),
+                _ => {
+                    variables.insert(name.to_string(), value.to_string());
+                }
+            }
+            comments.clear();
+            continue;
+        }
+
+        // Rules.
+        let Some((targets_part, rest)) = trimmed.split_once(':') else {
+            report.warn(Some(line_number), format!("line not recognized: {trimmed}"));
+            continue;
+        };
+        // Double-colon rules are handled as regular ones.
+        let rest = rest.strip_prefix(':').unwrap_or(rest);
+
+        // Inline description - `target: ## Description`.
+        let (rest, inline_description) = match rest.split_once("##") {
+            Some((rest, description)) => (rest, Some(description.trim().to_string())),
+            None => (rest, None),
+        };
+        let (prerequisites_part, inline_recipe) = match rest.split_once(';') {
+            Some((prerequisites, recipe)) => (prerequisites, Some(recipe.trim().to_string())),
+            None => (rest, None),
+        };
+
+        // Target-specific variables.
+        if split_make_assignment(prerequisites_part.trim()).is_some() {
+            report.warn(
+                Some(line_number),
+                format!("target-specific variable '{}' not translated", trimmed),
+            );
+            comments.clear();
+            continue;
+        }
+
+        let mut targets = Vec::new();
+        for target in targets_part.split_whitespace() {
+            // Special targets, e.g., `.PHONY`.
+            if target.starts_with('.') {
+                continue;
+            }
+            if target.contains('%') || target.contains('$') {
+                report.warn(
+                    Some(line_number),
+                    format!("pattern or computed target '{target}' not translated"),
+                );
+                continue;
+            }
+            targets.push(target.to_string());
+        }
+
+        let description = inline_description
+            .filter(|x| !x.is_empty())
+            .or_else(|| comments_description(&comments));
+        comments.clear();
+
+        let mut rule = MakeRule {
+            line: line_number,
+            targets,
+            prerequisites: prerequisites_part
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+            description,
+            recipe: Vec::new(),
+        };
+        if let Some(recipe) = inline_recipe {
+            rule.recipe.push((line_number, recipe));
+        }
+        rules.push(rule);
+    }
+
+    // Create commands.
+    let target_names: BTreeSet<String> = rules.iter().flat_map(|x| x.targets.clone()).collect();
+    for rule in rules {
+        for target in &rule.targets {
+            let mut command_lines = Vec::new();
+            let mut used_variables = BTreeSet::new();
+
+            // Prerequisites which are targets are run first.
+            for prerequisite in &rule.prerequisites {
+                if target_names.contains(prerequisite) {
+                    command_lines.push(format!("zxc {prerequisite}"));
+                } else {
+                    report.warn(
+                        Some(rule.line),
+                        format!("prerequisite '{prerequisite}' of '{target}' not translated"),
+                    );
+                }
+            }
+
+            for (line_number, recipe_line) in &rule.recipe {
+                let recipe_line = translate_recipe_prefixes(recipe_line);
+                let (translated, untranslated) =
+                    translate_make_line(&recipe_line, target, &variables, &mut used_variables);
+                for construct in untranslated {
+                    report.warn(
+                        Some(*line_number),
+                        format!("'{construct}' in '{target}' not translated"),
+                    );
+                }
+                command_lines.push(translated);
+            }
+
+            if command_lines.is_empty() {
+                report.warn(
+                    Some(rule.line),
+                    format!("target '{target}' has no recipe, skipped"),
+                );
+                continue;
+            }
+
+            let mut arguments = BTreeMap::new();
+            for variable in used_variables {
+                let value = variables.get(&variable).cloned().unwrap_or_default();
+                if value.contains('$') {
+                    report.warn(
+                        Some(rule.line),
+                        format!("default value of '{variable}' not translated: {value}"),
+                    );
+                }
+                arguments.insert(
+                    argument_name(&variable),
+                    variable_argument_def(&variable, Some(value)),
+                );
+            }
+
+            let def = command_def(command_lines, rule.description.clone(), arguments);
+            if report.defs.insert(target.clone(), def).is_some() {
+                report.warn(
+                    Some(rule.line),
+                    format!("target '{target}' defined multiple times, last rule is used"),
+                );
+            }
+        }
+    }
+
+    report
+}
+
+/// Split text into whitespace-separated tokens, respecting quotes.
+fn split_quoted(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut quote: Option<char> = None;
+    for c in text.chars() {
+        match quote {
+            Some(q) if c == q => {
+                quote = None;
+                token.push(c);
+            }
+            Some(_) => token.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                token.push(c);
+            }
+            None if c.is_whitespace() => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            None => token.push(c),
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    tokens
+}
+
+/// Get value of a quoted string literal.
+fn unquote(text: &str) -> Option<String> {
+    for quote in ['"', '\''] {
+        if text.len() >= 2 && text.starts_with(quote) && text.ends_with(quote) {
+            return Some(text[1..text.len() - 1].to_string());
+        }
+    }
+    None
+}
+
+/// Check if text is a valid recipe, parameter or variable name.
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        }
+        _ => false,
+    }
+}
+
+/// Justfile recipe.
+struct JustRecipe {
+    line: usize,
+    name: String,
+    parameters: Vec<(String, Option<String>)>,
+    dependencies: Vec<String>,
+    description: Option<String>,
+    body: Vec<(usize, String)>,
+}
+
+/// Translate interpolations in a justfile recipe line.
+/// Only references to parameters and variables are supported.
+fn translate_just_line(
+    line: &str,
+    names: &BTreeSet<String>,
+    used_names: &mut BTreeSet<String>,
+) -> (String, Vec<String>) {
+    let mut output = String::new();
+    let mut untranslated = Vec::new();
+    let mut rest = line;
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after_open = &rest[start + 2..];
+
+        // Escaped braces - `{{{{`.
+        if let Some(after_escape) = after_open.strip_prefix("{{") {
+            output.push_str("{{ '{{' }}");
+            rest = after_escape;
+            continue;
+        }
+
+        let Some(end) = after_open.find("}}") else {
+            output.push_str(&rest[start..]);
+            rest = "";
+            break;
+        };
+        let expression = after_open[..end].trim();
+        if names.contains(expression) {
+            output.push_str(&format!("{{{{ {} }}}}", argument_name(expression)));
+            used_names.insert(expression.to_string());
+        } else {
+            output.push_str(&raw_template(&rest[start..start + 2 + end + 2]));
+            untranslated.push(format!("{{{{ {expression} }}}}"));
+        }
+        rest = &after_open[end + 2..];
+    }
+    output.push_str(rest);
+    (output, untranslated)
+}
+
+/// Import commands from a justfile.
+/// Each recipe becomes a command, recipe parameters and used variables become arguments.
+pub fn import_justfile(content: &str) -> ImportReport {
+    let mut report = ImportReport::default();
+    let mut variables: BTreeMap<String, String> = BTreeMap::new();
+    let mut recipes: Vec<JustRecipe> = Vec::new();
+    let mut comments: Vec<String> = Vec::new();
+    let mut in_recipe = false;
+
+    for (index, line) in content.lines().enumerate() {
+        let line_number = index + 1;
+
+        // Recipe body - indented lines.
+        if in_recipe && (line.starts_with(' ') || line.starts_with('\t')) {
+            if let Some(recipe) = recipes.last_mut() {
+                recipe.body.push((line_number, line.to_string()));
+            }
+            continue;
+        }
+        in_recipe = false;
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            comments.clear();
+            continue;
+        }
+
+        // Comments are used as descriptions of following recipe.
+        if let Some(comment) = trimmed.strip_prefix('#') {
+            let comment = comment.trim();
+            if !comment.is_empty() {
+                comments.push(comment.to_string());
+            }
+            continue;
+        }
+
+        // Attributes.
+        if trimmed.starts_with('[') {
+            report.warn(
+                Some(line_number),
+                format!("attribute '{trimmed}' not translated"),
+            );
+            continue;
+        }
+
+        // Settings, aliases and modules.
+        let first_word = trimmed.split_whitespace().next().unwrap_or_default();
+        if ["set", "alias", "import", "mod"].contains(&first_word) {
+            report.warn(
+                Some(line_number),
+                format!("'{first_word}' statement not translated"),
+            );
+            comments.clear();
+            continue;
+        }
+
+        // Variable assignments.
+        if let Some((name, value)) = trimmed.split_once(":=") {
+            let mut name = name.trim();
+            if let Some(exported_name) = name.strip_prefix("export ") {
+                report.warn(
+                    Some(line_number),
+                    format!("export of '{}' not translated", exported_name.trim()),
+                );
+                name = exported_name.trim();
+            }
+            match unquote(value.trim()) {
+                Some(value) => {
+                    variables.insert(name.to_string(), value);
+                }
+                None => report.warn(
+                    Some(line_number),
+                    format!("value of '{name}' is an expression, not translated"),
+                ),
+            }
+            comments.clear();
+            continue;
+        }
+
+        // Recipe header - `name param='default': dependencies`.
+        let header = trimmed.strip_prefix('@').unwrap_or(trimmed);
+        let Some(colon_position) = header.find(':') else {
+            report.warn(Some(line_number), format!("line not recognized: {trimmed}"));
+            continue;
+        };
+        let signature = split_quoted(&header[..colon_position]);
+        let dependencies_part = &header[colon_position + 1..];
+        let Some(name) = signature.first().filter(|x| is_identifier(x)) else {
+            report.warn(Some(line_number), format!("line not recognized: {trimmed}"));
+            continue;
+        };
+
+        let mut parameters = Vec::new();
+        for parameter in &signature[1..] {
+            let mut parameter = parameter.as_str();
+            if let Some(x) = parameter.strip_prefix('$') {
+                report.warn(
+                    Some(line_number),
+                    format!("export of parameter '{x}' not translated"),
+                );
+                parameter = x;
+            }
+
+            let (parameter_name, default) = match parameter.split_once('=') {
+                Some((name, default)) => match unquote(default) {
+                    Some(x) => (name, Some(x)),
+                    None => {
+                        report.warn(
+                            Some(line_number),
+                            format!("default value of '{name}' is an expression, not translated"),
+                        );
+                        (name, None)
+                    }
+                },
+                None => (parameter, None),
+            };
+
+            // Variadic parameters are passed as a single value.
+            let (parameter_name, default) = match parameter_name.strip_prefix(['*', '+']) {
+                Some(x) => {
+                    report.warn(
+                        Some(line_number),
+                        format!("variadic parameter '{x}' is passed as a single value"),
+                    );
+                    let default = match parameter_name.starts_with('*') {
+                        true => default.or(Some(String::new())),
+                        false => default,
+                    };
+                    (x, default)
+                }
+                None => (parameter_name, default),
+            };
+            parameters.push((parameter_name.to_string(), default));
+        }
+
+        let mut dependencies = Vec::new();
+        for dependency in split_quoted(dependencies_part) {
+            if is_identifier(&dependency) {
+                dependencies.push(dependency);
+            } else {
+                report.warn(
+                    Some(line_number),
+                    format!("dependency '{dependency}' of '{name}' not translated"),
+                );
+            }
+        }
+
+        recipes.push(JustRecipe {
+            line: line_number,
+            name: name.clone(),
+            parameters,
+            dependencies,
+            description: comments_description(&comments),
+            body: Vec::new(),
+        });
+        comments.clear();
+        in_recipe = true;
+    }
+
+    // Create commands.
+    let recipe_names: BTreeSet<String> = recipes.iter().map(|x| x.name.clone()).collect();
+    for recipe in recipes {
+        // Shebang recipes are run by other interpreters.
+        let is_shebang = recipe
+            .body
+            .first()
+            .is_some_and(|(_, x)| x.trim_start().starts_with("#!"));
+        if is_shebang {
+            report.warn(
+                Some(recipe.line),
+                format!("shebang recipe '{}' not translated", recipe.name),
+            );
+            continue;
+        }
+
+        let mut names: BTreeSet<String> = variables.keys().cloned().collect();
+        names.extend(recipe.parameters.iter().map(|(name, _)| name.clone()));
+        let mut used_names = BTreeSet::new();
+
+        let mut command_lines = Vec::new();
+        for dependency in &recipe.dependencies {
+            if recipe_names.contains(dependency) {
+                command_lines.push(format!("zxc {dependency}"));
+            } else {
+                report.warn(
+                    Some(recipe.line),
+                    format!("dependency '{dependency}' of '{}' not found", recipe.name),
+                );
+            }
+        }
+        for (line_number, body_line) in &recipe.body {
+            let body_line = translate_recipe_prefixes(body_line.trim());
+            let (translated, untranslated) =
+                translate_just_line(&body_line, &names, &mut used_names);
+            for construct in untranslated {
+                report.warn(
+                    Some(*line_number),
+                    format!("'{construct}' in '{}' not translated", recipe.name),
+                );
+            }
+            command_lines.push(translated);
+        }
+        if command_lines.is_empty() {
+            report.warn(
+                Some(recipe.line),
+                format!("recipe '{}' has no body, skipped", recipe.name),
+            );
+            continue;
+        }
+
+        // Single parameter is kept positional, multiple are named to preserve their meaning.
+        let mut arguments = BTreeMap::new();
+        let is_positional = recipe.parameters.len() == 1;
+        if recipe.parameters.len() > 1 {
+            report.warn(
+                Some(recipe.line),
+                format!(
+                    "parameters of '{}' are mapped to named arguments",
+                    recipe.name
+                ),
+            );
+        }
+        for (parameter_name, default) in &recipe.parameters {
+            let name = argument_name(parameter_name);
+            let flags = match is_positional {
+                true => vec![name.clone()],
+                false => vec![long_flag(&name)],
+            };
+            arguments.insert(
+                name,
+                YamlArgumentDef {
+                    flags,
+                    default: default.clone().map(YamlDefaultValue::Value),
+                    ..Default::default()
+                },
+            );
+        }
+        for name in used_names {
+            if let Some(value) = variables.get(&name) {
+                if recipe.parameters.iter().any(|(x, _)| *x == name) {
+                    continue;
+                }
+                arguments.insert(
+                    argument_name(&name),
+                    variable_argument_def(&name, Some(value.clone())),
+                );
+            }
+        }
+
+        let def = command_def(command_lines, recipe.description, arguments);
+        report.defs.insert(recipe.name, def);
+    }
+
+    report
+}
+
+/// Import commands from `package.json` scripts.
+/// Scripts are run using `npm run`, descriptions are taken from `scripts-info`, if available.
+pub fn import_package_json(content: &str) -> Result<ImportReport, ZxcError> {
+    let mut report = ImportReport::default();
+    let package: serde_json::Value = match serde_json::from_str(content) {
+        Ok(x) => x,
+        Err(e) => return Err(ZxcError::from_json_error(content, e)),
+    };
+
+    let Some(scripts) = package.get("scripts").and_then(|x| x.as_object()) else {
+        report.warn(None, "no scripts found".to_string());
+        return Ok(report);
+    };
+    let scripts_info = package.get("scripts-info").and_then(|x| x.as_object());
+
+    for (name, script) in scripts {
+        let Some(script) = script.as_str() else {
+            report.warn(None, format!("script '{name}' is not a string, skipped"));
+            continue;
+        };
+
+        // Lifecycle scripts are run by `npm run` of related script.
+        let related_name = name
+            .strip_prefix("pre")
+            .or_else(|| name.strip_prefix("post"));
+        if related_name.is_some_and(|x| scripts.contains_key(x)) {
+            report.warn(
+                None,
+                format!("lifecycle script '{name}' is run as a part of related script"),
+            );
+            continue;
+        }
+
+        let description = match scripts_info
+            .and_then(|x| x.get(name))
+            .and_then(|x| x.as_str())
+        {
+            Some(x) => x.to_string(),
+            None => format!("Run `{script}`."),
+        };
+        let def = command_def(
+            vec![format!("npm run {name}")],
+            Some(description),
+            BTreeMap::new(),
+        );
+        report.defs.insert(name.clone(), def);
+    }
+
+    Ok(report)
+}
+
+/// Translate Go template references in a Taskfile command.
+/// Only references to variables (`{{.NAME}}`) are supported.
+fn translate_task_line(
+    line: &str,
+    variables: &BTreeSet<String>,
+    used_variables: &mut BTreeSet<String>,
+) -> (String, Vec<String>) {
+    let mut output = String::new();
+    let mut untranslated = Vec::new();
+    let mut rest = line;
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after_open = &rest[start + 2..];
+        let Some(end) = after_open.find("}}") else {
+            output.push_str(&rest[start..]);
+            rest = "";
+            break;
+        };
+
+        let expression = after_open[..end].trim();
+        match expression.strip_prefix('.') {
+            Some(name) if variables.contains(name) => {
+                output.push_str(&format!("{{{{ {} }}}}", argument_name(name)));
+                used_variables.insert(name.to_string());
+            }
+            _ => {
+                output.push_str(&raw_template(&rest[start..start + 2 + end + 2]));
+                untranslated.push(format!("{{{{{expression}}}}}"));
+            }
+        }
+        rest = &after_open[end + 2..];
+    }
+    output.push_str(rest);
+    (output, untranslated)
+}
+
+/// Collect static variables from Taskfile `vars` section.
+fn task_variables(
+    vars: Option<&serde_yaml::Value>,
+    report: &mut ImportReport,
+) -> BTreeMap<String, String> {
+    let mut variables = BTreeMap::new();
+    let Some(vars) = vars.and_then(|x| x.as_mapping()) else {
+        return variables;
+    };
+    for (name, value) in vars {
+        let Some(name) = name.as_str() else {
+            continue;
+        };
+        let value = match value {
+            serde_yaml::Value::String(x) => Some(x.clone()),
+            serde_yaml::Value::Number(x) => Some(x.to_string()),
+            serde_yaml::Value::Bool(x) => Some(x.to_string()),
+            _ => None,
+        };
+        match value {
+            Some(x) => {
+                variables.insert(name.to_string(), x);
+            }
+            None => report.warn(None, format!("dynamic variable '{name}' not translated")),
+        }
+    }
+    variables
+}
+
+/// Import commands from a Taskfile (https://taskfile.dev).
+/// Each task becomes a command, used variables become arguments.
+pub fn import_taskfile(content: &str) -> Result<ImportReport, ZxcError> {
+    /// Task keys which are translated.
+    const KNOWN_KEYS: [&str; 6] = ["desc", "summary", "cmds", "cmd", "deps", "vars"];
+
+    let mut report = ImportReport::default();
+    let taskfile: serde_yaml::Value = match serde_yaml::from_str(content) {
+        Ok(x) => x,
+        Err(e) => return Err(ZxcError::from_yaml_error(content, e)),
+    };
+
+    for key in ["includes", "env", "dotenv"] {
+        if taskfile.get(key).is_some() {
+            report.warn(None, format!("top-level '{key}' not translated"));
+        }
+    }
+    let global_variables = task_variables(taskfile.get("vars"), &mut report);
+
+    let Some(tasks) = taskfile.get("tasks").and_then(|x| x.as_mapping()) else {
+        report.warn(None, "no tasks found".to_string());
+        return Ok(report);
+    };
+
+    for (name, task) in tasks {
+        let Some(name) = name.as_str() else {
+            continue;
+        };
+
+        // Tasks can be defined as a single command, list of commands or a mapping.
+        let empty_mapping = serde_yaml::Mapping::new();
+        let (task_mapping, commands) = match task {
+            serde_yaml::Value::String(_) => (&empty_mapping, vec![task.clone()]),
+            serde_yaml::Value::Sequence(x) => (&empty_mapping, x.clone()),
+            serde_yaml::Value::Mapping(x) => {
+                let commands = match (x.get("cmds"), x.get("cmd")) {
+                    (Some(serde_yaml::Value::Sequence(cmds)), _) => cmds.clone(),
+                    (_, Some(cmd)) => vec![cmd.clone()],
+                    _ => vec![],
+                };
+                (x, commands)
+            }
+            _ => {
+                report.warn(None, format!("task '{name}' not recognized, skipped"));
+                continue;
+            }
+        };
+
+        for key in task_mapping.keys().filter_map(|x| x.as_str()) {
+            if !KNOWN_KEYS.contains(&key) {
+                report.warn(None, format!("'{key}' of task '{name}' not translated"));
+            }
+        }
+
+        let mut variables = global_variables.clone();
+        variables.extend(task_variables(task_mapping.get("vars"), &mut report));
+        let mut variable_names: BTreeSet<String> = variables.keys().cloned().collect();
+        variable_names.insert("CLI_ARGS".to_string());
+        let mut used_variables = BTreeSet::new();
+
+        // Dependencies are run first, then commands.
+        let dependencies = task_mapping
+            .get("deps")
+            .and_then(|x| x.as_sequence())
+            .cloned()
+            .unwrap_or_default();
+        let mut lines = Vec::new();
+        for dependency in &dependencies {
+            lines.push(match dependency {
+                serde_yaml::Value::String(x) => Some(format!("zxc {x}")),
+                x => x
+                    .get("task")
+                    .and_then(|x| x.as_str())
+                    .map(|x| format!("zxc {x}")),
+            });
+        }
+        for command in &commands {
+            lines.push(match command {
+                serde_yaml::Value::String(x) => Some(x.clone()),
+                serde_yaml::Value::Mapping(x) => match (x.get("task"), x.get("cmd")) {
+                    (Some(task), _) => task.as_str().map(|x| format!("zxc {x}")),
+                    (_, Some(cmd)) => cmd.as_str().map(str::to_string),
+                    _ => None,
+                },
+                _ => None,
+            });
+        }
+
+        let mut command_lines = Vec::new();
+        for line in lines {
+            let Some(line) = line else {
+                report.warn(None, format!("command of task '{name}' not translated"));
+                continue;
+            };
+            let (translated, untranslated) =
+                translate_task_line(&line, &variable_names, &mut used_variables);
+            for construct in untranslated {
+                report.warn(None, format!("'{construct}' in '{name}' not translated"));
+            }
+            command_lines.push(translated);
+        }
+        if command_lines.is_empty() {
+            report.warn(None, format!("task '{name}' has no commands, skipped"));
+            continue;
+        }
+
+        let mut arguments = BTreeMap::new();
+        for variable in used_variables {
+            // Additional CLI arguments are passed as a single positional value.
+            if variable == "CLI_ARGS" {
+                arguments.insert(
+                    argument_name(&variable),
+                    YamlArgumentDef {
+                        flags: vec![argument_name(&variable)],
+                        default: Some(YamlDefaultValue::Value(String::new())),
+                        description: Some("Additional arguments.".to_string()),
+                        ..Default::default()
+                    },
+                );
+                continue;
+            }
+            let default = variables.get(&variable).cloned();
+            arguments.insert(
+                argument_name(&variable),
+                variable_argument_def(&variable, default),
+            );
+        }
+
+        let description = task_mapping
+            .get("desc")
+            .or_else(|| task_mapping.get("summary"))
+            .and_then(|x| x.as_str())
+            .map(|x| x.trim().to_string());
+        let def = command_def(command_lines, description, arguments);
+        report.defs.insert(name.to_string(), def);
+    }
+
+    Ok(report)
+}
+
+/// Import commands from a file.
+/// Format is determined based on file name.
+pub fn import_defs_from_file(file_path: PathBuf) -> Result<ImportReport, ZxcError> {
+    let format = match ImportFormat::from_path(&file_path) {
+        Some(x) => x,
+        None => {
+            return Err(ZxcError::Parse {
+                path: Some(file_path),
+                message: "unknown import format - Makefile, justfile, package.json and Taskfile.yml are supported".to_string(),
+                snippet: None,
+            });
+        }
+    };
+
+    let content = match fs::read_to_string(&file_path) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(ZxcError::Io {
+                path: Some(file_path),
+                source: e,
+            });
+        }
+    };
+
+    let report = match format {
+        ImportFormat::Makefile => import_makefile(&content),
+        ImportFormat::Justfile => import_justfile(&content),
+        ImportFormat::PackageJson => {
+            import_package_json(&content).map_err(|e| e.with_path(&file_path))?
+        }
+        ImportFormat::Taskfile => import_taskfile(&content).map_err(|e| e.with_path(&file_path))?,
+    };
+    Ok(report)
+}
+
+#[cfg(test)]
+mod import_format_tests {
+    use super::ImportFormat;
+    use std::path::Path;
+
+    #[test]
+    fn known_formats() {
+        assert_eq!(
+            ImportFormat::from_path(Path::new("dir/Makefile")),
+            Some(ImportFormat::Makefile)
+        );
+        assert_eq!(
+            ImportFormat::from_path(Path::new("rules.mk")),
+            Some(ImportFormat::Makefile)
+        );
+        assert_eq!(
+            ImportFormat::from_path(Path::new("justfile")),
+            Some(ImportFormat::Justfile)
+        );
+        assert_eq!(
+            ImportFormat::from_path(Path::new("package.json")),
+            Some(ImportFormat::PackageJson)
+        );
+        assert_eq!(
+            ImportFormat::from_path(Path::new("Taskfile.yml")),
+            Some(ImportFormat::Taskfile)
+        );
+    }
+
+    #[test]
+    fn unknown_format() {
+        assert_eq!(ImportFormat::from_path(Path::new("build.gradle")), None);
+    }
+}
+
+#[cfg(test)]
+mod import_makefile_tests {
+    use super::import_makefile;
+    use crate::command_resolver::resolve_command;
+    use crate::yaml_command_defs::YamlDefaultValue;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn targets_and_variables() {
+        let content = "PREFIX ?= /usr/local\n\
+                       \n\
+                       .PHONY: build install\n\
+                       \n\
+                       # Build the project.\n\
+                       build:\n\
+                       \t@cargo build\n\
+                       \n\
+                       install: build ## Install the project.\n\
+                       \tinstall app $(PREFIX)/bin\n\
+                       \t-rm -f $$HOME/app.log\n";
+
+        let report = import_makefile(content);
+        assert!(report.warnings.is_empty());
+        assert_eq!(report.defs.len(), 2);
+
+        let build = report.defs.get("build").unwrap();
+        assert_eq!(build.command, "cargo build");
+        assert_eq!(build.description.as_deref(), Some("Build the project."));
+
+        let install = report.defs.get("install").unwrap();
+        assert_eq!(
+            install.command,
+            "zxc build &&\ninstall app {{ prefix }}/bin &&\nrm -f $HOME/app.log || true"
+        );
+        assert_eq!(install.description.as_deref(), Some("Install the project."));
+        let prefix = install.arguments.as_ref().unwrap().get("prefix").unwrap();
+        assert_eq!(prefix.flags, vec!["--prefix"]);
+        assert_eq!(
+            prefix.default,
+            Some(YamlDefaultValue::Value("/usr/local".to_string()))
+        );
+    }
+
+    #[test]
+    fn untranslated_constructs() {
+        let content = "include other.mk\n\
+                       \n\
+                       %.o: %.c\n\
+                       \tcc -c $<\n\
+                       \n\
+                       app: main.o\n\
+                       \tcc -o $@ $^\n";
+
+        let report = import_makefile(content);
+        assert_eq!(report.defs.len(), 1);
+        assert_eq!(report.defs.get("app").unwrap().command, "cc -o app $^");
+        assert_eq!(report.warnings.len(), 4);
+        assert!(report.warnings[0].starts_with("line 1:"));
+    }
+
+    #[test]
+    fn template_syntax() {
+        let content = "NAME = app\n\
+                       \n\
+                       ps:\n\
+                       \tdocker ps --filter name=$(NAME) --format \"{{.Names}}\" {%\n";
+
+        let report = import_makefile(content);
+        let ps = report.defs.get("ps").unwrap();
+        assert_eq!(
+            ps.command,
+            "docker ps --filter name={{ name }}{% raw %} --format \"{{.Names}}\" {%{% endraw %}"
+        );
+
+        // Template syntax is kept literally.
+        let arguments = BTreeMap::from([("name".to_string(), "app".to_string())]);
+        assert_eq!(
+            resolve_command(&ps.command, &arguments).unwrap(),
+            "docker ps --filter name=app --format \"{{.Names}}\" {%"
+        );
+    }
+}
+
+#[cfg(test)]
+mod import_justfile_tests {
+    use super::import_justfile;
+    use crate::yaml_command_defs::YamlDefaultValue;
+
+    #[test]
+    fn recipes_and_parameters() {
+        let content = r#"
+version := "1.0"
+
+# Build the project.
+build profile="debug":
+    cargo build --profile {{profile}}
+
+# Release the project.
+release: build
+    @echo "Releasing {{ version }}"
+"#;
+
+        let report = import_justfile(content);
+        assert!(report.warnings.is_empty());
+
+        let build = report.defs.get("build").unwrap();
+        assert_eq!(build.command, "cargo build --profile {{ profile }}");
+        assert_eq!(build.description.as_deref(), Some("Build the project."));
+        let profile = build.arguments.as_ref().unwrap().get("profile").unwrap();
+        assert_eq!(profile.flags, vec!["profile"]);
+        assert_eq!(
+            profile.default,
+            Some(YamlDefaultValue::Value("debug".to_string()))
+        );
+
+        let release = report.defs.get("release").unwrap();
+        assert_eq!(
+            release.command,
+            "zxc build &&\necho \"Releasing {{ version }}\""
+        );
+        let version = release.arguments.as_ref().unwrap().get("version").unwrap();
+        assert_eq!(version.flags, vec!["--arg-version"]);
+        assert_eq!(
+            version.default,
+            Some(YamlDefaultValue::Value("1.0".to_string()))
+        );
+    }
+
+    #[test]
+    fn multiple_parameters_named() {
+        let content = "deploy env region:\n    ./deploy.sh {{env}} {{region}}\n";
+
+        let report = import_justfile(content);
+        assert_eq!(report.warnings.len(), 1);
+        let arguments = report
+            .defs
+            .get("deploy")
+            .unwrap()
+            .arguments
+            .clone()
+            .unwrap();
+        assert_eq!(arguments.get("env").unwrap().flags, vec!["--env"]);
+        assert_eq!(arguments.get("region").unwrap().flags, vec!["--region"]);
+    }
+
+    #[test]
+    fn untranslated_constructs() {
+        let content = r#"
+set shell := ["bash", "-c"]
+
+run:
+    echo {{ uppercase("x") }}
+
+script:
+    #!/usr/bin/env python3
+    print("Hello")
+"#;
+
+        let report = import_justfile(content);
+        assert_eq!(report.defs.len(), 1);
+        assert_eq!(
+            report.defs.get("run").unwrap().command,
+            r#"echo {% raw %}{{ uppercase("x") }}{% endraw %}"#
+        );
+        assert_eq!(report.warnings.len(), 3);
+    }
+}
+
+#[cfg(test)]
+mod import_package_json_tests {
+    use super::import_package_json;
+    use crate::error::ZxcError;
+
+    #[test]
+    fn scripts() {
+        let content = r#"{
+          "scripts": {
+            "build": "tsc",
+            "prebuild": "rm -rf dist",
+            "test": "jest"
+          },
+          "scripts-info": {
+            "test": "Run unit tests."
+          }
+        }"#;
+
+        let report = import_package_json(content).unwrap();
+        assert_eq!(report.defs.len(), 2);
+        assert_eq!(report.warnings.len(), 1);
+
+        let build = report.defs.get("build").unwrap();
+        assert_eq!(build.command, "npm run build");
+        assert_eq!(build.description.as_deref(), Some("Run `tsc`."));
+        let test = report.defs.get("test").unwrap();
+        assert_eq!(test.description.as_deref(), Some("Run unit tests."));
+    }
+
+    #[test]
+    fn invalid_json() {
+        let result = import_package_json("{ \"scripts\": ");
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Parse { .. })));
+    }
+}
+
+#[cfg(test)]
+mod import_taskfile_tests {
+    use super::import_taskfile;
+    use crate::yaml_command_defs::YamlDefaultValue;
+
+    #[test]
+    fn tasks() {
+        let content = r#"
+version: '3'
+vars:
+  GREETING: Hello
+tasks:
+  greet:
+    desc: Greet someone.
+    deps: [prepare]
+    cmds:
+      - echo "{{.GREETING}} {{.CLI_ARGS}}"
+      - task: cleanup
+  prepare: mkdir -p out
+  cleanup:
+    - rm -rf out
+"#;
+
+        let report = import_taskfile(content).unwrap();
+        assert!(report.warnings.is_empty());
+        assert_eq!(report.defs.len(), 3);
+
+        let greet = report.defs.get("greet").unwrap();
+        assert_eq!(
+            greet.command,
+            "zxc prepare &&\necho \"{{ greeting }} {{ cli_args }}\" &&\nzxc cleanup"
+        );
+        assert_eq!(greet.description.as_deref(), Some("Greet someone."));
+        let arguments = greet.arguments.as_ref().unwrap();
+        assert_eq!(arguments.get("cli_args").unwrap().flags, vec!["cli_args"]);
+        assert_eq!(
+            arguments.get("greeting").unwrap().default,
+            Some(YamlDefaultValue::Value("Hello".to_string()))
+        );
+        assert_eq!(report.defs.get("cleanup").unwrap().command, "rm -rf out");
+    }
+
+    #[test]
+    fn untranslated_constructs() {
+        let content = r#"
+tasks:
+  build:
+    sources: ["*.go"]
+    cmds:
+      - go build -o {{.OUTPUT | default "app"}}
+"#;
+
+        let report = import_taskfile(content).unwrap();
+        assert_eq!(report.warnings.len(), 2);
+        assert!(report.defs.contains_key("build"));
+    }
+}
@@ -0,0 +1,572 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::yaml_command_defs::{DefinitionOrigin, find_definition_snippet, find_template_snippet};
+
+/// ANSI escape sequences used for highlighting.
+const STYLE_ERROR: &str = "\x1b[1;31m";
+const STYLE_WARNING: &str = "\x1b[1;33m";
+const STYLE_GUTTER: &str = "\x1b[1;34m";
+const STYLE_BOLD: &str = "\x1b[1m";
+const STYLE_RESET: &str = "\x1b[0m";
+
+/// Fragment of a source pointing to an offending location.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SourceSnippet {
+    /// Path to the source file, if source comes from a file.
+    pub path: Option<PathBuf>,
+    /// Description of the source, if it's not a file, e.g., template of a command.
+    pub context: Option<String>,
+    /// Content of the offending line.
+    pub line_content: String,
+    /// Line number, starting from 1.
+    pub line: usize,
+    /// Column number, starting from 1.
+    pub column: usize,
+    /// Number of highlighted characters.
+    pub length: usize,
+}
+
+impl SourceSnippet {
+    /// Create snippet from full source content and location in it.
+    /// Line directly after the last one is allowed, as it's reported for unexpected end of input.
+    /// Returns `None` if location is outside of provided source.
+    pub fn new(
+        path: Option<&Path>,
+        source: &str,
+        line: usize,
+        column: usize,
+        length: usize,
+    ) -> Option<Self> {
+        let line_index = line.checked_sub(1)?;
+        let line_content = match source.lines().nth(line_index) {
+            Some(x) => x,
+            None if line_index == source.lines().count() => "",
+            None => return None,
+        };
+        Some(SourceSnippet {
+            path: path.map(Path::to_path_buf),
+            context: None,
+            line_content: line_content.to_string(),
+            line,
+            column: column.max(1),
+            length: length.max(1),
+        })
+    }
+
+    /// Create snippet from full source content and byte offset in it.
+    pub fn from_offset(
+        path: Option<&Path>,
+        source: &str,
+        offset: usize,
+        length: usize,
+    ) -> Option<Self> {
+        let preceding = source.get(..offset)?;
+        let line = preceding.matches('\n').count() + 1;
+        let line_start = preceding.rfind('\n').map(|x| x + 1).unwrap_or(0);
+        let column = preceding[line_start..].chars().count() + 1;
+        Self::new(path, source, line, column, length)
+    }
+
+    /// Render snippet with location header and markers under offending fragment.
+    fn render(&self, color: bool) -> String {
+        let (gutter, error, reset) = match color {
+            true => (STYLE_GUTTER, STYLE_ERROR, STYLE_RESET),
+            false => ("", "", ""),
+        };
+
+        let location = match (&self.path, &self.context) {
+            (Some(path), _) => format!("{}:{}:{}", path.display(), self.line, self.column),
+            (None, Some(context)) => format!("{}:{} of {context}", self.line, self.column),
+            (None, None) => format!("{}:{}", self.line, self.column),
+        };
+        let line_number = self.line.to_string();
+        let padding = " ".repeat(line_number.len());
+
+        // Markers are limited to the end of line.
+        let line_length = self.line_content.chars().count();
+        let marker_offset = (self.column - 1).min(line_length);
+        let marker_length = self
+            .length
+            .min(line_length.saturating_sub(marker_offset))
+            .max(1);
+
+        let mut output = String::new();
+        output.push_str(&format!("{gutter}{padding}-->{reset} {location}\n"));
+        output.push_str(&format!("{gutter}{padding} |{reset}\n"));
+        output.push_str(&format!(
+            "{gutter}{line_number} |{reset} {}\n",
+            self.line_content
+        ));
+        output.push_str(&format!(
+            "{gutter}{padding} |{reset} {}{error}{}{reset}",
+            " ".repeat(marker_offset),
+            "^".repeat(marker_length)
+        ));
+        output
+    }
+}
+
+/// `zxc` error.
+#[derive(Debug)]
+pub enum ZxcError {
+    /// Failed to find definition files.
+    Discovery { path: PathBuf, message: String },
+    /// Failed to parse definition file.
+    Parse {
+        path: Option<PathBuf>,
+        message: String,
+        snippet: Option<Box<SourceSnippet>>,
+    },
+    /// Command definition is invalid.
+    Validation {
+        command: String,
+        origin: Option<DefinitionOrigin>,
+        message: String,
+        snippet: Option<Box<SourceSnippet>>,
+    },
+    /// Failed to process command template.
+    Template {
+        command: String,
+        origin: Option<DefinitionOrigin>,
+        message: String,
+        snippet: Option<Box<SourceSnippet>>,
+    },
+    /// Invalid value of command argument provided by user.
+    Usage { command: String, message: String },
+    /// Failed to run command.
+    Execution { command: String, source: io::Error },
+    /// Invalid `zxc` setting.
+    Settings { source: String, message: String },
+    /// Other I/O failure.
+    Io {
+        path: Option<PathBuf>,
+        source: io::Error,
+    },
+}
+
+impl ZxcError {
+    /// Create error from YAML parsing error.
+    pub fn from_yaml_error(source: &str, error: serde_yaml::Error) -> Self {
+        let mut message = error.to_string();
+        let mut snippet = None;
+        if let Some(location) = error.location() {
+            // Location is already provided in snippet.
+            let location_suffix =
+                format!(" at line {} column {}", location.line(), location.column());
+            message = message.replacen(&location_suffix, "", 1);
+            snippet = SourceSnippet::new(None, source, location.line(), location.column(), 1);
+        }
+
+        ZxcError::Parse {
+            path: None,
+            message,
+            snippet: snippet.map(Box::new),
+        }
+    }
+
+    /// Create error from TOML parsing error.
+    pub fn from_toml_error(source: &str, error: toml::de::Error) -> Self {
+        let snippet = match error.span() {
+            Some(span) => SourceSnippet::from_offset(None, source, span.start, span.len()),
+            None => None,
+        };
+
+        ZxcError::Parse {
+            path: None,
+            message: error.message().to_string(),
+            snippet: snippet.map(Box::new),
+        }
+    }
+
+    /// Create error from JSON parsing error.
+    pub fn from_json_error(source: &str, error: serde_json::Error) -> Self {
+        // Location is already provided in snippet.
+        let location_suffix = format!(" at line {} column {}", error.line(), error.column());
+        let message = error.to_string().replacen(&location_suffix, "", 1);
+        let snippet = SourceSnippet::new(None, source, error.line(), error.column(), 1);
+
+        ZxcError::Parse {
+            path: None,
+            message,
+            snippet: snippet.map(Box::new),
+        }
+    }
+
+    /// Attach path of the file error originates from.
+    pub fn with_path(self, file_path: &Path) -> Self {
+        let file_path = Some(file_path.to_path_buf());
+        match self {
+            ZxcError::Parse {
+                message, snippet, ..
+            } => ZxcError::Parse {
+                path: file_path.clone(),
+                message,
+                snippet: snippet.map(|x| {
+                    Box::new(SourceSnippet {
+                        path: file_path,
+                        ..*x
+                    })
+                }),
+            },
+            ZxcError::Io { source, .. } => ZxcError::Io {
+                path: file_path,
+                source,
+            },
+            other => other,
+        }
+    }
+
+    /// Create error from template processing error.
+    pub fn from_template_error(command: &str, template: &str, error: &minijinja::Error) -> Self {
+        let message = match error.detail() {
+            Some(detail) => format!("{}: {detail}", error.kind()),
+            None => error.kind().to_string(),
+        };
+        let snippet = match error.range() {
+            Some(range) => SourceSnippet::from_offset(None, template, range.start, range.len()),
+            None => match error.line() {
+                Some(line) => SourceSnippet::new(None, template, line, 1, 1),
+                None => None,
+            },
+        };
+
+        ZxcError::Template {
+            command: command.to_string(),
+            origin: None,
+            message,
+            snippet: snippet.map(Box::new),
+        }
+    }
+
+    /// Attach location of the command definition error originates from.
+    /// Validation errors without snippet point to the command definition.
+    /// Template snippets are mapped into the definition file, if possible.
+    pub fn with_origin(self, origin: Option<&DefinitionOrigin>) -> Self {
+        match self {
+            ZxcError::Validation {
+                command,
+                message,
+                snippet,
+                ..
+            } => ZxcError::Validation {
+                command,
+                snippet: match (snippet, origin) {
+                    (None, Some(origin)) => find_definition_snippet(origin, &[]).map(Box::new),
+                    (snippet, _) => snippet,
+                },
+                origin: origin.cloned(),
+                message,
+            },
+            ZxcError::Template {
+                command,
+                message,
+                snippet,
+                ..
+            } => ZxcError::Template {
+                command,
+                snippet: match (snippet, origin) {
+                    (Some(x), Some(origin)) if x.path.is_none() && x.context.is_none() => {
+                        match find_template_snippet(origin, &x) {
+                            Some(mapped) => Some(Box::new(mapped)),
+                            None => Some(Box::new(SourceSnippet {
+                                context: Some(format!("template at '{origin}'")),
+                                ..*x
+                            })),
+                        }
+                    }
+                    (snippet, _) => snippet,
+                },
+                origin: origin.cloned(),
+                message,
+            },
+            other => other,
+        }
+    }
+
+    /// Exit code of `zxc` failing with the error.
+    /// Usage errors use the same exit code as command line parsing errors.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ZxcError::Usage { .. } => 2,
+            _ => 1,
+        }
+    }
+
+    /// Render error message, optionally using ANSI colors.
+    pub fn render(&self, color: bool) -> String {
+        let (error, bold, reset) = match color {
+            true => (STYLE_ERROR, STYLE_BOLD, STYLE_RESET),
+            false => ("", "", ""),
+        };
+
+        let (summary, snippet) = match self {
+            ZxcError::Discovery { path, message } => (
+                format!(
+                    "failed to find definition files in '{}': {message}",
+                    path.display()
+                ),
+                None,
+            ),
+            ZxcError::Parse {
+                path,
+                message,
+                snippet,
+            } => {
+                let summary = match path {
+                    Some(path) => {
+                        format!("failed to parse '{}': {message}", path.display())
+                    }
+                    None => format!("failed to parse definitions: {message}"),
+                };
+                (summary, snippet.as_ref())
+            }
+            ZxcError::Validation {
+                command,
+                origin,
+                message,
+                snippet,
+            } => (
+                format!(
+                    "invalid definition of command {}: {message}",
+                    describe_command(command, origin.as_ref())
+                ),
+                snippet.as_ref(),
+            ),
+            ZxcError::Template {
+                command,
+                origin,
+                message,
+                snippet,
+            } => (
+                format!(
+                    "invalid template of command {}: {message}",
+                    describe_command(command, origin.as_ref())
+                ),
+                snippet.as_ref(),
+            ),
+            ZxcError::Usage { command, message } => (
+                format!("invalid usage of command '{command}': {message}"),
+                None,
+            ),
+            ZxcError::Execution { command, source } => {
+                (format!("failed to run command '{command}': {source}"), None)
+            }
+            ZxcError::Settings { source, message } => {
+                (format!("invalid setting in '{source}': {message}"), None)
+            }
+            ZxcError::Io { path, source } => {
+                let summary = match path {
+                    Some(path) => format!("'{}': {source}", path.display()),
+                    None => source.to_string(),
+                };
+                (summary, None)
+            }
+        };
+
+        let mut output = format!("{error}error{reset}{bold}: {summary}{reset}");
+        if let Some(snippet) = snippet {
+            output.push('\n');
+            output.push_str(&snippet.render(color));
+        }
+        output
+    }
+}
+
+/// Describe command along with location of its definition, if known.
+fn describe_command(command: &str, origin: Option<&DefinitionOrigin>) -> String {
+    match origin {
+        Some(origin) => format!("'{command}' from '{origin}'"),
+        None => format!("'{command}'"),
+    }
+}
+
+/// Render warning message, optionally using ANSI colors.
+pub fn render_warning(message: &str, color: bool) -> String {
+    let (warning, bold, reset) = match color {
+        true => (STYLE_WARNING, STYLE_BOLD, STYLE_RESET),
+        false => ("", "", ""),
+    };
+    format!("{warning}warning{reset}{bold}: {message}{reset}")
+}
+
+impl fmt::Display for ZxcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(false))
+    }
+}
+
+impl std::error::Error for ZxcError {}
+
+impl From<io::Error> for ZxcError {
+    fn from(source: io::Error) -> Self {
+        ZxcError::Io { path: None, source }
+    }
+}
+
+#[cfg(test)]
+mod source_snippet_tests {
+    use super::SourceSnippet;
+    use std::path::Path;
+
+    #[test]
+    fn from_offset() {
+        let source = "first line\nsecond line\n";
+        let snippet = SourceSnippet::from_offset(None, source, 18, 4).unwrap();
+        assert_eq!(snippet.line, 2);
+        assert_eq!(snippet.column, 8);
+        assert_eq!(snippet.line_content, "second line");
+    }
+
+    #[test]
+    fn out_of_range() {
+        let source = "single line";
+        assert!(SourceSnippet::new(None, source, 3, 1, 1).is_none());
+        assert!(SourceSnippet::new(None, source, 0, 1, 1).is_none());
+        assert!(SourceSnippet::from_offset(None, source, 100, 1).is_none());
+    }
+
+    #[test]
+    fn render() {
+        let source = "run:\n  command: echo {{ name }\n";
+        let path = Path::new("zxc.yml");
+        let snippet = SourceSnippet::new(Some(path), source, 2, 17, 4).unwrap();
+        let expected = concat!(
+            " --> zxc.yml:2:17\n",
+            "  |\n",
+            "2 |   command: echo {{ name }\n",
+            "  |                 ^^^^"
+        );
+        assert_eq!(snippet.render(false), expected);
+    }
+}
+
+#[cfg(test)]
+mod zxc_error_tests {
+    use super::ZxcError;
+    use crate::yaml_command_defs::DefinitionOrigin;
+    use minijinja::Environment;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn yaml_error() {
+        let source = "run:\n  command: [\n";
+        let yaml_error = serde_yaml::from_str::<serde_yaml::Value>(source).unwrap_err();
+        let error = ZxcError::from_yaml_error(source, yaml_error).with_path(Path::new("zxc.yml"));
+
+        match &error {
+            ZxcError::Parse { path, snippet, .. } => {
+                assert_eq!(path.as_deref(), Some(Path::new("zxc.yml")));
+                let snippet = snippet.as_ref().unwrap();
+                assert_eq!(snippet.path.as_deref(), Some(Path::new("zxc.yml")));
+            }
+            _ => panic!("Unexpected error: {error:?}"),
+        }
+        assert!(!error.to_string().contains(" at line "));
+    }
+
+    #[test]
+    fn toml_error() {
+        let source = "[run]\ncommand = 1\n";
+        let toml_error = toml::from_str::<toml::Table>("[run]\ncommand = \n").unwrap_err();
+        let error = ZxcError::from_toml_error(source, toml_error);
+
+        match &error {
+            ZxcError::Parse { snippet, .. } => {
+                assert_eq!(snippet.as_ref().unwrap().line, 2);
+            }
+            _ => panic!("Unexpected error: {error:?}"),
+        }
+    }
+
+    #[test]
+    fn json_error() {
+        let source = "{\n  \"run\": {\n    \"command\": }\n}\n";
+        let json_error = serde_json::from_str::<serde_json::Value>(source).unwrap_err();
+        let error = ZxcError::from_json_error(source, json_error);
+
+        match &error {
+            ZxcError::Parse { snippet, .. } => {
+                assert_eq!(snippet.as_ref().unwrap().line, 3);
+            }
+            _ => panic!("Unexpected error: {error:?}"),
+        }
+        assert!(!error.to_string().contains(" at line "));
+    }
+
+    #[test]
+    fn template_error() {
+        let template = "echo {{ arg1 {{ arg2 }}";
+        let mut env = Environment::new();
+        let template_error = env.add_template("command", template).unwrap_err();
+        let error = ZxcError::from_template_error("greet", template, &template_error);
+
+        match &error {
+            ZxcError::Template {
+                command, snippet, ..
+            } => {
+                assert_eq!(command, "greet");
+                let snippet = snippet.as_ref().unwrap();
+                assert_eq!(snippet.line, 1);
+                assert_eq!(snippet.line_content, template);
+            }
+            _ => panic!("Unexpected error: {error:?}"),
+        }
+    }
+
+    #[test]
+    fn template_error_origin() {
+        let template = "echo {{ name }";
+        let mut env = Environment::new();
+        let template_error = env.add_template("command", template).unwrap_err();
+        let origin = DefinitionOrigin {
+            path: PathBuf::from(".zxc.yml"),
+            line: Some(3),
+        };
+        let error = ZxcError::from_template_error("greet", template, &template_error)
+            .with_origin(Some(&origin));
+
+        assert!(
+            error
+                .to_string()
+                .starts_with("error: invalid template of command 'greet' from '.zxc.yml:3': ")
+        );
+        // Definition file is not available - snippet is labelled with the origin instead.
+        assert!(error.to_string().contains(" of template at '.zxc.yml:3'\n"));
+    }
+
+    #[test]
+    fn render_color() {
+        let error = ZxcError::Validation {
+            command: "greet".to_string(),
+            origin: None,
+            message: "no flags are defined".to_string(),
+            snippet: None,
+        };
+        assert_eq!(
+            error.to_string(),
+            "error: invalid definition of command 'greet': no flags are defined"
+        );
+        assert!(error.render(true).contains("\x1b["));
+    }
+
+    #[test]
+    fn exit_code() {
+        let error = ZxcError::Usage {
+            command: "logs".to_string(),
+            message: "invalid value 'db' for argument 'service'".to_string(),
+        };
+        assert_eq!(error.exit_code(), 2);
+        assert_eq!(
+            error.to_string(),
+            "error: invalid usage of command 'logs': invalid value 'db' for argument 'service'"
+        );
+
+        let error = ZxcError::Discovery {
+            path: PathBuf::from("."),
+            message: "no definition files found".to_string(),
+        };
+        assert_eq!(error.exit_code(), 1);
+    }
+}
@@ -0,0 +1,408 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use glob::{MatchOptions, Pattern};
+use serde::Deserialize;
+
+use crate::config::Config;
+use crate::error::ZxcError;
+
+/// Name of external mapping file, placed in app home.
+pub const EXTERNAL_MAPPING_NAME: &str = "external.yml";
+
+/// Entry of external mapping file.
+/// Entry applies if all provided patterns match.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct ExternalMappingEntry {
+    /// Glob pattern matching CWD or one of its parents. `~` is expanded to home directory.
+    path: Option<String>,
+    /// Glob pattern matching URL of one of git remotes.
+    git_remote: Option<String>,
+    /// Glob pattern matching repository name.
+    repository: Option<String>,
+    /// Definition files, relative to app home.
+    definitions: Vec<PathBuf>,
+}
+
+/// Information about git repository containing CWD.
+#[derive(Debug, Default)]
+struct RepositoryInfo {
+    /// Repository root directory.
+    root: PathBuf,
+    /// URLs of all remotes.
+    remote_urls: Vec<String>,
+}
+
+impl RepositoryInfo {
+    /// Possible repository names - taken from remote URLs and root directory name.
+    fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .remote_urls
+            .iter()
+            .filter_map(|x| x.trim_end_matches('/').rsplit(['/', ':']).next())
+            .map(|x| x.trim_end_matches(".git").to_string())
+            .collect();
+        if let Some(x) = self.root.file_name() {
+            names.push(x.to_string_lossy().to_string());
+        }
+        names
+    }
+}
+
+/// Read URLs of remotes from git config file.
+fn read_remote_urls(git_config_path: &Path) -> Vec<String> {
+    let Ok(content) = fs::read_to_string(git_config_path) else {
+        return vec![];
+    };
+
+    let mut remote_urls = vec![];
+    let mut in_remote_section = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_remote_section = line.starts_with("[remote ");
+            continue;
+        }
+        if !in_remote_section {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=')
+            && key.trim() == "url"
+        {
+            remote_urls.push(value.trim().trim_matches('"').to_string());
+        }
+    }
+    remote_urls
+}
+
+/// Find git repository containing specified directory.
+/// Worktrees and submodules (`.git` file pointing to git directory) are supported.
+fn find_repository(directory_path: &Path) -> Option<RepositoryInfo> {
+    for root in directory_path.ancestors() {
+        let dot_git = root.join(".git");
+        let git_dir = if dot_git.is_dir() {
+            dot_git
+        } else if dot_git.is_file() {
+            let content = fs::read_to_string(&dot_git).ok()?;
+            let git_dir = content.trim().strip_prefix("gitdir:")?.trim();
+            root.join(git_dir)
+        } else {
+            continue;
+        };
+
+        // Worktrees keep configuration in common directory.
+        let common_dir = match fs::read_to_string(git_dir.join("commondir")) {
+            Ok(x) => git_dir.join(x.trim()),
+            Err(_) => git_dir,
+        };
+        return Some(RepositoryInfo {
+            root: root.to_path_buf(),
+            remote_urls: read_remote_urls(&common_dir.join("config")),
+        });
+    }
+    None
+}
+
+/// Compile glob pattern, expanding leading `~` to home directory.
+fn compile_pattern(pattern: &str, home_dir: Option<&Path>) -> Result<Pattern, String> {
+    let pattern = match (pattern.strip_prefix('~'), home_dir) {
+        (Some(rest), Some(home_dir)) => format!("{}{rest}", home_dir.display()),
+        _ => pattern.to_string(),
+    };
+    Pattern::new(&pattern).map_err(|e| format!("invalid pattern '{pattern}': {e}"))
+}
+
+impl ExternalMappingEntry {
+    /// Check if entry applies to specified directory.
+    fn matches(
+        &self,
+        directory_path: &Path,
+        repository: Option<&RepositoryInfo>,
+        home_dir: Option<&Path>,
+    ) -> Result<bool, String> {
+        if self.path.is_none() && self.git_remote.is_none() && self.repository.is_none() {
+            return Err("entry must define 'path', 'git_remote' or 'repository'".to_string());
+        }
+
+        if let Some(path) = &self.path {
+            let pattern = compile_pattern(path, home_dir)?;
+            let options = MatchOptions {
+                require_literal_separator: true,
+                ..Default::default()
+            };
+            let is_match = directory_path
+                .ancestors()
+                .any(|x| pattern.matches_path_with(x, options));
+            if !is_match {
+                return Ok(false);
+            }
+        }
+
+        if let Some(git_remote) = &self.git_remote {
+            let pattern = compile_pattern(git_remote, None)?;
+            let is_match =
+                repository.is_some_and(|x| x.remote_urls.iter().any(|url| pattern.matches(url)));
+            if !is_match {
+                return Ok(false);
+            }
+        }
+
+        if let Some(name) = &self.repository {
+            let pattern = compile_pattern(name, None)?;
+            let is_match =
+                repository.is_some_and(|x| x.names().iter().any(|name| pattern.matches(name)));
+            if !is_match {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// Find definition files mapped to CWD by external mapping file (`<app home>/external.yml`).
+/// Files are returned in order of entries - later files take precedence.
+/// Returns empty container if mapping file doesn't exist.
+pub fn find_mapped_def_files(config: &Config) -> Result<Vec<PathBuf>, ZxcError> {
+    let mapping_path = config.app_home.join(EXTERNAL_MAPPING_NAME);
+    if !mapping_path.is_file() {
+        return Ok(vec![]);
+    }
+
+    // Load mapping file.
+    let content = match fs::read_to_string(&mapping_path) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(ZxcError::Io {
+                path: Some(mapping_path),
+                source: e,
+            });
+        }
+    };
+    let entries: Vec<ExternalMappingEntry> = match serde_yaml::from_str(&content) {
+        Ok(x) => x,
+        Err(e) => return Err(ZxcError::from_yaml_error(&content, e).with_path(&mapping_path)),
+    };
+
+    // Find matching entries.
+    let repository = find_repository(&config.cwd);
+    let mut found_files = vec![];
+    for entry in entries {
+        let is_match =
+            match entry.matches(&config.cwd, repository.as_ref(), config.home_dir.as_deref()) {
+                Ok(x) => x,
+                Err(message) => {
+                    return Err(ZxcError::Parse {
+                        path: Some(mapping_path),
+                        message,
+                        snippet: None,
+                    });
+                }
+            };
+        if !is_match {
+            continue;
+        }
+
+        for definition in entry.definitions {
+            let definition_path = config.app_home.join(definition);
+            if !definition_path.is_file() {
+                return Err(ZxcError::Discovery {
+                    path: definition_path,
+                    message: format!("file referenced by '{EXTERNAL_MAPPING_NAME}' not found"),
+                });
+            }
+            found_files.push(definition_path);
+        }
+    }
+    Ok(found_files)
+}
+
+#[cfg(test)]
+mod find_repository_tests {
+    use super::find_repository;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn repository_with_remotes() {
+        let dir = tempdir().unwrap();
+        let git_dir = dir.path().join(".git");
+        fs::create_dir(&git_dir).unwrap();
+        let git_config = "[core]\n\tbare = false\n\
+                          [remote \"origin\"]\n\turl = git@github.com:acme/service-a.git\n\
+                          [remote \"fork\"]\n\turl = https://github.com/me/service-fork\n";
+        fs::write(git_dir.join("config"), git_config).unwrap();
+        let nested_dir = dir.path().join("src");
+        fs::create_dir(&nested_dir).unwrap();
+
+        let repository = find_repository(&nested_dir).unwrap();
+        assert_eq!(repository.root, dir.path());
+        assert_eq!(
+            repository.remote_urls,
+            vec![
+                "git@github.com:acme/service-a.git",
+                "https://github.com/me/service-fork"
+            ]
+        );
+        let names = repository.names();
+        assert!(names.contains(&"service-a".to_string()));
+        assert!(names.contains(&"service-fork".to_string()));
+    }
+
+    #[test]
+    fn worktree() {
+        let dir = tempdir().unwrap();
+        let main_git_dir = dir.path().join("main").join(".git");
+        let worktree_git_dir = main_git_dir.join("worktrees").join("feature");
+        fs::create_dir_all(&worktree_git_dir).unwrap();
+        fs::write(
+            main_git_dir.join("config"),
+            "[remote \"origin\"]\n\turl = git@github.com:acme/service-a.git\n",
+        )
+        .unwrap();
+        fs::write(worktree_git_dir.join("commondir"), "../..\n").unwrap();
+
+        let worktree_dir = dir.path().join("feature");
+        fs::create_dir(&worktree_dir).unwrap();
+        fs::write(
+            worktree_dir.join(".git"),
+            format!("gitdir: {}\n", worktree_git_dir.display()),
+        )
+        .unwrap();
+
+        let repository = find_repository(&worktree_dir).unwrap();
+        assert_eq!(
+            repository.remote_urls,
+            vec!["git@github.com:acme/service-a.git"]
+        );
+    }
+
+    #[test]
+    fn no_repository() {
+        let dir = tempdir().unwrap();
+        assert!(find_repository(dir.path()).is_none_or(|x| x.root != dir.path()));
+    }
+}
+
+#[cfg(test)]
+mod find_mapped_def_files_tests {
+    use super::{EXTERNAL_MAPPING_NAME, find_mapped_def_files};
+    use crate::config::Config;
+    use crate::error::ZxcError;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    /// Create configuration with provided mapping and empty definition files.
+    fn setup(cwd: &Path, app_home: &Path, mapping: &str, definitions: &[&str]) -> Config {
+        fs::write(app_home.join(EXTERNAL_MAPPING_NAME), mapping).unwrap();
+        for definition in definitions {
+            fs::write(app_home.join(definition), "").unwrap();
+        }
+        Config {
+            cwd: cwd.to_path_buf(),
+            app_home: app_home.to_path_buf(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn no_mapping_file() {
+        let cwd = tempdir().unwrap();
+        let app_home = tempdir().unwrap();
+        let config = Config {
+            cwd: cwd.path().to_path_buf(),
+            app_home: app_home.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        let result = find_mapped_def_files(&config).unwrap();
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn path_pattern() {
+        let home = tempdir().unwrap();
+        let cwd = home.path().join("src").join("billing-service");
+        fs::create_dir_all(cwd.join("nested")).unwrap();
+        let app_home = tempdir().unwrap();
+        let mapping = r#"
+          - path: ~/src/*-service
+            definitions: [service.yml]
+          - path: ~/*-service
+            definitions: [other.yml]
+        "#;
+        let mut config = setup(
+            &cwd.join("nested"),
+            app_home.path(),
+            mapping,
+            &["service.yml", "other.yml"],
+        );
+        config.home_dir = Some(home.path().to_path_buf());
+
+        let result = find_mapped_def_files(&config).unwrap();
+        assert_eq!(result, vec![app_home.path().join("service.yml")]);
+    }
+
+    #[test]
+    fn git_remote_and_repository() {
+        let cwd = tempdir().unwrap();
+        let git_dir = cwd.path().join(".git");
+        fs::create_dir(&git_dir).unwrap();
+        fs::write(
+            git_dir.join("config"),
+            "[remote \"origin\"]\n\turl = git@github.com:acme/service-a.git\n",
+        )
+        .unwrap();
+        let app_home = tempdir().unwrap();
+        let mapping = r#"
+          - git_remote: "*github.com?acme/*"
+            definitions: [acme.yml]
+          - repository: service-a
+            definitions: [service-a.yml]
+          - repository: service-b
+            definitions: [service-b.yml]
+        "#;
+        let config = setup(
+            cwd.path(),
+            app_home.path(),
+            mapping,
+            &["acme.yml", "service-a.yml", "service-b.yml"],
+        );
+
+        let result = find_mapped_def_files(&config).unwrap();
+        assert_eq!(
+            result,
+            vec![
+                app_home.path().join("acme.yml"),
+                app_home.path().join("service-a.yml")
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_definition_file() {
+        let cwd = tempdir().unwrap();
+        let app_home = tempdir().unwrap();
+        let mapping = format!(
+            "- path: {}\n  definitions: [missing.yml]\n",
+            cwd.path().display()
+        );
+        let config = setup(cwd.path(), app_home.path(), &mapping, &[]);
+
+        let result = find_mapped_def_files(&config);
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Discovery { .. })));
+    }
+
+    #[test]
+    fn entry_without_patterns() {
+        let cwd = tempdir().unwrap();
+        let app_home = tempdir().unwrap();
+        let mapping = "- definitions: [service.yml]\n";
+        let config = setup(cwd.path(), app_home.path(), mapping, &["service.yml"]);
+
+        let result = find_mapped_def_files(&config);
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Parse { path: Some(_), .. })));
+    }
+}
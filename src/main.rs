@@ -1,43 +1,269 @@
+mod argument_choices;
 mod cli;
 mod command_resolver;
 mod command_runner;
 mod config;
+mod def_checker;
 mod def_file_finder;
+mod def_file_loader;
+mod def_importer;
+mod error;
+mod external_mapping;
+mod schema;
+mod settings;
+mod signal_forwarder;
+mod virtual_defs;
 mod yaml_command_defs;
 mod zxc_command_defs;
 
 use std::collections::BTreeMap;
-use std::io::{Error, ErrorKind};
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::PathBuf;
 
-use cli::build_cli;
-use command_resolver::resolve_command;
-use command_runner::run_command;
+use argument_choices::resolve_choices;
+use cli::{build_cli, describe_resolved_command, parse_global_args};
+use command_resolver::resolve_command_template;
+use command_runner::{COMMAND_ENV_NAME, exit_code, run_command, run_command_with_retry};
 use config::Config;
+use def_checker::check_definitions;
 use def_file_finder::find_definition_files;
-use yaml_command_defs::{load_yaml_defs_from_file, merge_yaml_defs};
-use zxc_command_defs::{find_zxc_command_def, load_zxc_defs};
+use def_file_loader::load_defs_from_file;
+use def_importer::import_defs_from_file;
+use error::{ZxcError, render_warning};
+use schema::{SCHEMA_ID, definition_file_schema_string};
+use settings::{ColorMode, Verbosity};
+use yaml_command_defs::{find_override_warnings, merge_yaml_defs};
+use zxc_command_defs::{
+    ZxcCommandDefs, find_template_warnings, find_zxc_command_def, load_zxc_defs,
+};
 
-fn main() -> Result<(), Error> {
-    // Initialize and load configuration.
-    let config = Config::new()?;
+fn main() {
+    // Color mode is updated once settings are loaded.
+    let mut color_mode = ColorMode::Auto;
+    match run(&mut color_mode) {
+        Ok(exit_code) => std::process::exit(exit_code),
+        Err(e) => {
+            let color = color_mode.enabled(std::io::stderr().is_terminal());
+            eprintln!("{}", e.render(color));
+            std::process::exit(e.exit_code());
+        }
+    }
+}
+
+/// Ask user for confirmation before running a command.
+/// Confirmation is not requested if input is not a terminal.
+fn confirm_command(command: &str) -> Result<bool, ZxcError> {
+    if !std::io::stdin().is_terminal() {
+        return Ok(true);
+    }
+
+    eprint!("Run `{command}`? [y/N] ");
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Ask user to select argument value from allowed values.
+/// Nothing is selected if input is not a terminal.
+/// Value can be selected by its number or typed directly.
+fn select_choice(argument_name: &str, choices: &[String]) -> Result<Option<String>, ZxcError> {
+    if !std::io::stdin().is_terminal() || choices.is_empty() {
+        return Ok(None);
+    }
 
+    for (index, choice) in choices.iter().enumerate() {
+        eprintln!("{:>3}) {choice}", index + 1);
+    }
+    eprint!("Select {argument_name} [1-{}]: ", choices.len());
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    let answer = answer.trim();
+    let selected = match answer.parse::<usize>() {
+        Ok(x) if (1..=choices.len()).contains(&x) => Some(choices[x - 1].clone()),
+        _ => choices.iter().find(|x| *x == answer).cloned(),
+    };
+    Ok(selected)
+}
+
+/// Validate definition files and return exit code.
+fn run_check(config: &Config) -> i32 {
+    let report = check_definitions(config);
+
+    let color = config
+        .settings
+        .color
+        .value
+        .enabled(std::io::stderr().is_terminal());
+    for warning in &report.warnings {
+        eprintln!("{}\n", render_warning(warning, color));
+    }
+    for error in &report.errors {
+        eprintln!("{}\n", error.render(color));
+    }
+
+    if report.errors.is_empty() {
+        for definition_file in &report.definition_files {
+            println!("{}: OK", definition_file.display());
+        }
+        0
+    } else {
+        eprintln!("Found {} problem(s)", report.errors.len());
+        1
+    }
+}
+
+/// Print definition file translated from a file of another tool.
+fn run_import(file_path: PathBuf) -> Result<i32, ZxcError> {
+    let report = import_defs_from_file(file_path.clone())?;
+
+    let color = std::io::stderr().is_terminal();
+    for warning in &report.warnings {
+        let warning = format!("{}: {warning}", file_path.display());
+        eprintln!("{}", render_warning(&warning, color));
+    }
+
+    let definitions = match serde_yaml::to_string(&report.defs) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(ZxcError::Parse {
+                path: None,
+                message: e.to_string(),
+                snippet: None,
+            });
+        }
+    };
+    println!("# yaml-language-server: $schema={SCHEMA_ID}");
+    println!("# Imported from '{}'.", file_path.display());
+    print!("{definitions}");
+    Ok(0)
+}
+
+/// Find and load command definitions.
+/// Warnings are printed according to verbosity.
+fn load_command_defs(
+    config: &Config,
+    verbosity: Verbosity,
+    color: bool,
+) -> Result<ZxcCommandDefs, ZxcError> {
     // Find definition files.
-    let definition_files = find_definition_files(&config);
+    let definition_files = find_definition_files(config)?;
     if definition_files.is_empty() {
-        return Err(Error::new(ErrorKind::NotFound, "No definition files found"));
+        return Err(ZxcError::Discovery {
+            path: config.cwd.clone(),
+            message: "no definition files found".to_string(),
+        });
+    }
+
+    if verbosity >= Verbosity::Verbose {
+        for definition_file in &definition_files {
+            eprintln!("zxc: using '{}'", definition_file.path.display());
+        }
     }
 
-    // Load command data.
     // First load in YAML-faced structure.
     let mut yaml_command_defs_vec = vec![];
-    for definition_file_path in definition_files {
-        let defs_from_file = load_yaml_defs_from_file(definition_file_path)?;
+    for definition_file in definition_files {
+        let defs_from_file = load_defs_from_file(definition_file.path, definition_file.is_overlay)?;
         yaml_command_defs_vec.push(defs_from_file);
     }
     let yaml_command_defs = merge_yaml_defs(yaml_command_defs_vec);
+    if verbosity >= Verbosity::Verbose {
+        for warning in find_override_warnings(&yaml_command_defs) {
+            eprintln!("{}", render_warning(&warning, color));
+        }
+    }
 
     // Then transform to app-faced structure
     let zxc_command_defs = load_zxc_defs(yaml_command_defs)?;
+    if verbosity >= Verbosity::Normal {
+        for zxc_command_def in &zxc_command_defs {
+            for warning in find_template_warnings(zxc_command_def) {
+                eprintln!("{}", render_warning(&warning, color));
+            }
+        }
+    }
+    Ok(zxc_command_defs)
+}
+
+/// Print where a command is defined and which definitions it overrides.
+fn run_which(zxc_command_defs: ZxcCommandDefs, command_name: &str) -> Result<i32, ZxcError> {
+    let zxc_command_def = match find_zxc_command_def(zxc_command_defs, command_name) {
+        Some(x) => x,
+        None => {
+            return Err(ZxcError::Validation {
+                command: command_name.to_string(),
+                origin: None,
+                message: "command definition not found".to_string(),
+                snippet: None,
+            });
+        }
+    };
+
+    let origin = match &zxc_command_def.origin {
+        Some(x) => x.to_string(),
+        None => "unknown location".to_string(),
+    };
+    println!("{command_name}: {origin}");
+    println!("  Command: {}", zxc_command_def.command);
+    for shadowed in &zxc_command_def.shadowed {
+        println!("  Shadows: {shadowed}");
+    }
+    Ok(0)
+}
+
+/// Run `zxc` and return exit code.
+fn run(color_mode: &mut ColorMode) -> Result<i32, ZxcError> {
+    // Parse options available before loading definitions.
+    let global_args = parse_global_args(std::env::args_os());
+
+    // Print schema - it doesn't depend on configuration.
+    if global_args.print_schema {
+        print!("{}", definition_file_schema_string());
+        return Ok(0);
+    }
+
+    // Import commands - it doesn't depend on configuration.
+    if let Some(file_path) = global_args.import {
+        return run_import(file_path);
+    }
+
+    // Initialize and load configuration.
+    let mut config = Config::new()?;
+    if let Some(directory_path) = &global_args.directory {
+        config.set_directory(directory_path)?;
+    }
+    if let Some(file_path) = &global_args.file {
+        config.set_definition_file(file_path);
+    }
+    let settings = &config.settings;
+    *color_mode = settings.color.value;
+    let color = color_mode.enabled(std::io::stderr().is_terminal());
+    let verbosity = match global_args.verbose {
+        true => Verbosity::Verbose,
+        false => settings.verbosity.value,
+    };
+
+    // Print settings instead of running a command.
+    if global_args.config_show {
+        print!("{}", settings.describe());
+        return Ok(0);
+    }
+
+    // Validate definitions instead of running a command.
+    if global_args.check {
+        return Ok(run_check(&config));
+    }
+
+    // Explain command origin instead of running it.
+    if let Some(command_name) = global_args.which {
+        let zxc_command_defs = load_command_defs(&config, verbosity, color)?;
+        return run_which(zxc_command_defs, &command_name);
+    }
+
+    // Load command data.
+    let zxc_command_defs = load_command_defs(&config, verbosity, color)?;
 
     // Build CLI and parse arguments.
     let cli_command = build_cli(&zxc_command_defs);
@@ -48,29 +274,111 @@ fn main() -> Result<(), Error> {
         let zxc_command_def = match find_zxc_command_def(zxc_command_defs, subcommand_name) {
             Some(x) => x,
             None => {
-                let error_message = format!("Command definition not found: {subcommand_name}");
-                return Err(Error::new(ErrorKind::NotFound, error_message));
+                return Err(ZxcError::Validation {
+                    command: subcommand_name.to_string(),
+                    origin: None,
+                    message: "command definition not found".to_string(),
+                    snippet: None,
+                });
             }
         };
 
+        // Private commands can be run only by other commands.
+        if zxc_command_def.private && std::env::var_os(COMMAND_ENV_NAME).is_none() {
+            return Err(ZxcError::Validation {
+                command: subcommand_name.to_string(),
+                origin: None,
+                message: "command is private, it can be run only by other commands".to_string(),
+                snippet: None,
+            });
+        }
+        if let Some(deprecated) = &zxc_command_def.deprecated
+            && verbosity >= Verbosity::Normal
+        {
+            let warning = format!("command '{subcommand_name}' is deprecated: {deprecated}");
+            eprintln!("{}", render_warning(&warning, color));
+        }
+
         // Process required command data.
         let unresolved_command = &zxc_command_def.command;
         let mut arguments: BTreeMap<String, String> = BTreeMap::new();
-        for id in subcommand_args.ids() {
-            let value: &String = subcommand_args.get_one(id.as_str()).unwrap();
-            arguments.insert(id.to_string(), value.to_string());
+        for zxc_argument_def in &zxc_command_def.arguments {
+            let name = &zxc_argument_def.name;
+            if let Some(value) = subcommand_args.get_one::<String>(name) {
+                arguments.insert(name.clone(), value.clone());
+            }
         }
+        let shell = match &zxc_command_def.shell {
+            Some(x) => x,
+            None => &settings.shell.value,
+        };
+        let mut env = zxc_command_def.env.clone();
+        env.insert(COMMAND_ENV_NAME.to_string(), subcommand_name.to_string());
+
+        // Resolve values of arguments which are not provided, check values against choices.
+        resolve_choices(
+            &zxc_command_def,
+            &mut arguments,
+            shell,
+            &config.cwd,
+            &env,
+            config.cache_dir.as_deref(),
+            select_choice,
+        )?;
 
         // Resolve command.
-        let resolved_command = match resolve_command(unresolved_command, &arguments) {
-            Ok(x) => x,
-            Err(e) => return Err(Error::new(ErrorKind::InvalidData, e)),
-        };
+        let resolved_command =
+            match resolve_command_template(&zxc_command_def, unresolved_command, &arguments) {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(ZxcError::from_template_error(
+                        subcommand_name,
+                        unresolved_command,
+                        &e,
+                    )
+                    .with_origin(zxc_command_def.origin.as_ref()));
+                }
+            };
+
+        // Print resolved command instead of running it.
+        if global_args.dry_run {
+            let description = describe_resolved_command(
+                &zxc_command_def,
+                subcommand_args,
+                &arguments,
+                &resolved_command,
+            );
+            print!("{description}");
+            return Ok(0);
+        }
+
+        // Ask for confirmation, if enabled.
+        if settings.confirm.value && !confirm_command(&resolved_command)? {
+            eprintln!("zxc: command not confirmed, aborting");
+            return Ok(1);
+        }
 
         // Run command.
-        let run_status = run_command(&resolved_command);
-        let exit_code = run_status?.code().expect("Process terminated by signal");
-        std::process::exit(exit_code);
+        if verbosity >= Verbosity::Verbose {
+            eprintln!("zxc: running `{resolved_command}`");
+        }
+        let env = &env;
+        let run_status = match &zxc_command_def.retry {
+            Some(retry) => {
+                run_command_with_retry(&resolved_command, shell, &config.cwd, env, retry)
+            }
+            None => run_command(&resolved_command, shell, &config.cwd, env),
+        };
+        let run_status = match run_status {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(ZxcError::Execution {
+                    command: subcommand_name.to_string(),
+                    source: e,
+                });
+            }
+        };
+        return Ok(exit_code(&run_status));
     }
-    Ok(())
+    Ok(0)
 }
@@ -0,0 +1,54 @@
+use schemars::generate::SchemaSettings;
+use serde_json::Value;
+
+use crate::yaml_command_defs::YamlDefinitionFile;
+
+/// URL of published schema.
+pub const SCHEMA_ID: &str =
+    "https://raw.githubusercontent.com/arkjedrz/zxc/main/docs/zxc.schema.json";
+
+/// Generate JSON Schema of definition file.
+/// Schema is derived from YAML-faced definitions, draft 7 is used for compatibility with editors.
+pub fn definition_file_schema() -> Value {
+    let generator = SchemaSettings::draft07().into_generator();
+    let mut schema = generator.into_root_schema_for::<YamlDefinitionFile>();
+    schema.insert("$id".to_string(), Value::from(SCHEMA_ID));
+    schema.insert("title".to_string(), Value::from("zxc definition file"));
+    schema.insert(
+        "description".to_string(),
+        Value::from(
+            "Commands run by `zxc`. Command name is used as a key, `null` removes a command.",
+        ),
+    );
+    schema.to_value()
+}
+
+/// Generate JSON Schema of definition file as a pretty-printed string.
+pub fn definition_file_schema_string() -> String {
+    let schema = definition_file_schema();
+    serde_json::to_string_pretty(&schema).expect("Failed to serialize schema") + "\n"
+}
+
+#[cfg(test)]
+mod definition_file_schema_tests {
+    use super::{definition_file_schema, definition_file_schema_string};
+
+    #[test]
+    fn published_schema_up_to_date() {
+        // Regenerate using `zxc --print-schema > docs/zxc.schema.json`.
+        let published = include_str!("../docs/zxc.schema.json");
+        assert_eq!(definition_file_schema_string(), published);
+    }
+
+    #[test]
+    fn command_properties() {
+        let schema = definition_file_schema();
+        let command_properties = &schema["definitions"]["YamlCommandDef"]["properties"];
+        assert!(command_properties["command"].is_object());
+        assert!(command_properties["arguments"].is_object());
+        assert_eq!(
+            schema["definitions"]["YamlCommandDef"]["additionalProperties"],
+            false
+        );
+    }
+}
@@ -0,0 +1,409 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::error::ZxcError;
+
+/// Origin of a setting value.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum SettingSource {
+    /// Built-in default.
+    #[default]
+    Default,
+    /// User configuration file.
+    File(PathBuf),
+    /// Environment variable.
+    Env(&'static str),
+}
+
+impl fmt::Display for SettingSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingSource::Default => write!(f, "default"),
+            SettingSource::File(path) => write!(f, "{}", path.display()),
+            SettingSource::Env(name) => write!(f, "${name}"),
+        }
+    }
+}
+
+/// Setting value with its origin.
+#[derive(Clone, Debug, Default)]
+pub struct Setting<T> {
+    pub value: T,
+    pub source: SettingSource,
+}
+
+impl<T> Setting<T> {
+    fn set(&mut self, value: T, source: SettingSource) {
+        self.value = value;
+        self.source = source;
+    }
+}
+
+/// When to use colors in output.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorMode {
+    /// Only if output is a terminal.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    /// Check if colors should be used for output.
+    pub fn enabled(&self, is_terminal: bool) -> bool {
+        match self {
+            ColorMode::Auto => is_terminal,
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+        }
+    }
+}
+
+impl fmt::Display for ColorMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorMode::Auto => write!(f, "auto"),
+            ColorMode::Always => write!(f, "always"),
+            ColorMode::Never => write!(f, "never"),
+        }
+    }
+}
+
+/// Amount of diagnostic output.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+#[serde(rename_all = "lowercase")]
+pub enum Verbosity {
+    /// Only errors.
+    Quiet,
+    /// Errors and warnings.
+    #[default]
+    Normal,
+    /// Errors, warnings and information about performed actions.
+    Verbose,
+}
+
+impl fmt::Display for Verbosity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verbosity::Quiet => write!(f, "quiet"),
+            Verbosity::Normal => write!(f, "normal"),
+            Verbosity::Verbose => write!(f, "verbose"),
+        }
+    }
+}
+
+/// Default shell, same as used by `run_script`.
+pub fn default_shell() -> String {
+    match cfg!(windows) {
+        true => "cmd.exe".to_string(),
+        false => "sh".to_string(),
+    }
+}
+
+/// YAML-faced discovery settings.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct YamlDiscoverySettings {
+    parent_walk: Option<bool>,
+    extra_search_dirs: Option<Vec<PathBuf>>,
+}
+
+/// YAML-faced user configuration file.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct YamlSettings {
+    shell: Option<String>,
+    color: Option<ColorMode>,
+    discovery: Option<YamlDiscoverySettings>,
+    confirm: Option<bool>,
+    verbosity: Option<Verbosity>,
+}
+
+/// `zxc` user settings.
+#[derive(Clone, Debug)]
+pub struct Settings {
+    /// Shell used to run commands.
+    pub shell: Setting<String>,
+    /// When to use colors in output.
+    pub color: Setting<ColorMode>,
+    /// Search parent directories if definition file is not found in CWD.
+    pub parent_walk: Setting<bool>,
+    /// Additional directories searched for definition files.
+    pub extra_search_dirs: Setting<Vec<PathBuf>>,
+    /// Ask for confirmation before running a command.
+    pub confirm: Setting<bool>,
+    /// Amount of diagnostic output.
+    pub verbosity: Setting<Verbosity>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            shell: Setting {
+                value: default_shell(),
+                source: SettingSource::Default,
+            },
+            color: Setting::default(),
+            parent_walk: Setting::default(),
+            extra_search_dirs: Setting::default(),
+            confirm: Setting::default(),
+            verbosity: Setting::default(),
+        }
+    }
+}
+
+/// Parse value of an environment variable.
+fn parse_env_value<T: for<'de> Deserialize<'de>>(name: &str, value: &str) -> Result<T, ZxcError> {
+    match serde_yaml::from_str(value) {
+        Ok(x) => Ok(x),
+        Err(_) => Err(ZxcError::Settings {
+            source: format!("${name}"),
+            message: format!("invalid value '{value}'"),
+        }),
+    }
+}
+
+impl Settings {
+    /// Apply settings from user configuration file.
+    /// Relative search directories are resolved against the file location.
+    fn apply_file(&mut self, file_path: &Path) -> Result<(), ZxcError> {
+        let content = match fs::read_to_string(file_path) {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(ZxcError::Io {
+                    path: Some(file_path.to_path_buf()),
+                    source: e,
+                });
+            }
+        };
+        // Empty file is a valid configuration.
+        if content.trim().is_empty() {
+            return Ok(());
+        }
+        let yaml_settings: YamlSettings = match serde_yaml::from_str(&content) {
+            Ok(x) => x,
+            Err(e) => return Err(ZxcError::from_yaml_error(&content, e).with_path(file_path)),
+        };
+
+        let source = SettingSource::File(file_path.to_path_buf());
+        if let Some(x) = yaml_settings.shell {
+            self.shell.set(x, source.clone());
+        }
+        if let Some(x) = yaml_settings.color {
+            self.color.set(x, source.clone());
+        }
+        if let Some(x) = yaml_settings.confirm {
+            self.confirm.set(x, source.clone());
+        }
+        if let Some(x) = yaml_settings.verbosity {
+            self.verbosity.set(x, source.clone());
+        }
+        if let Some(discovery) = yaml_settings.discovery {
+            if let Some(x) = discovery.parent_walk {
+                self.parent_walk.set(x, source.clone());
+            }
+            if let Some(x) = discovery.extra_search_dirs {
+                let base_dir = file_path.parent().unwrap_or(Path::new(""));
+                let dirs = x.into_iter().map(|x| base_dir.join(x)).collect();
+                self.extra_search_dirs.set(dirs, source.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply settings from `ZXC_*` environment variables.
+    fn apply_env<F>(&mut self, env: F) -> Result<(), ZxcError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        if let Some(x) = env("ZXC_SHELL") {
+            self.shell.set(x, SettingSource::Env("ZXC_SHELL"));
+        }
+        if let Some(x) = env("ZXC_COLOR") {
+            let value = parse_env_value("ZXC_COLOR", &x)?;
+            self.color.set(value, SettingSource::Env("ZXC_COLOR"));
+        }
+        if let Some(x) = env("ZXC_PARENT_WALK") {
+            let value = parse_env_value("ZXC_PARENT_WALK", &x)?;
+            self.parent_walk
+                .set(value, SettingSource::Env("ZXC_PARENT_WALK"));
+        }
+        if let Some(x) = env("ZXC_EXTRA_SEARCH_DIRS") {
+            let dirs = std::env::split_paths(&x)
+                .filter(|x| !x.as_os_str().is_empty())
+                .collect();
+            self.extra_search_dirs
+                .set(dirs, SettingSource::Env("ZXC_EXTRA_SEARCH_DIRS"));
+        }
+        if let Some(x) = env("ZXC_CONFIRM") {
+            let value = parse_env_value("ZXC_CONFIRM", &x)?;
+            self.confirm.set(value, SettingSource::Env("ZXC_CONFIRM"));
+        }
+        if let Some(x) = env("ZXC_VERBOSITY") {
+            let value = parse_env_value("ZXC_VERBOSITY", &x)?;
+            self.verbosity
+                .set(value, SettingSource::Env("ZXC_VERBOSITY"));
+        }
+        Ok(())
+    }
+
+    /// Load settings.
+    /// Configuration files are applied in order - later files take precedence.
+    /// Missing files are skipped. Environment variables take precedence over files.
+    pub fn load<F>(config_files: &[PathBuf], env: F) -> Result<Self, ZxcError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut settings = Settings::default();
+        for config_file in config_files {
+            if config_file.is_file() {
+                settings.apply_file(config_file)?;
+            }
+        }
+        settings.apply_env(env)?;
+        Ok(settings)
+    }
+
+    /// Describe effective settings and their sources.
+    pub fn describe(&self) -> String {
+        let extra_search_dirs: Vec<String> = self
+            .extra_search_dirs
+            .value
+            .iter()
+            .map(|x| x.display().to_string())
+            .collect();
+        let lines = [
+            ("shell", self.shell.value.clone(), &self.shell.source),
+            ("color", self.color.value.to_string(), &self.color.source),
+            (
+                "discovery.parent_walk",
+                self.parent_walk.value.to_string(),
+                &self.parent_walk.source,
+            ),
+            (
+                "discovery.extra_search_dirs",
+                format!("[{}]", extra_search_dirs.join(", ")),
+                &self.extra_search_dirs.source,
+            ),
+            (
+                "confirm",
+                self.confirm.value.to_string(),
+                &self.confirm.source,
+            ),
+            (
+                "verbosity",
+                self.verbosity.value.to_string(),
+                &self.verbosity.source,
+            ),
+        ];
+
+        let mut output = String::new();
+        for (name, value, source) in lines {
+            output.push_str(&format!("{name}: {value} ({source})\n"));
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod settings_load_tests {
+    use super::{ColorMode, SettingSource, Settings, Verbosity};
+    use crate::error::ZxcError;
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    #[test]
+    fn defaults() {
+        let settings = Settings::load(&[PathBuf::from("/non/existent.yml")], |_| None).unwrap();
+        assert_eq!(settings.color.value, ColorMode::Auto);
+        assert_eq!(settings.verbosity.value, Verbosity::Normal);
+        assert!(!settings.parent_walk.value);
+        assert!(!settings.confirm.value);
+        assert_eq!(settings.shell.source, SettingSource::Default);
+    }
+
+    #[test]
+    fn files_precedence() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("first.yml");
+        let second = dir.path().join("second.yml");
+        let first_content = r#"
+          shell: bash
+          color: never
+          discovery:
+            parent_walk: true
+            extra_search_dirs: [commands]
+        "#;
+        fs::write(&first, first_content).unwrap();
+        fs::write(&second, "color: always\nverbosity: quiet\n").unwrap();
+
+        let settings = Settings::load(&[first.clone(), second.clone()], |_| None).unwrap();
+        assert_eq!(settings.shell.value, "bash");
+        assert_eq!(settings.shell.source, SettingSource::File(first.clone()));
+        assert_eq!(settings.color.value, ColorMode::Always);
+        assert_eq!(settings.color.source, SettingSource::File(second));
+        assert_eq!(settings.verbosity.value, Verbosity::Quiet);
+        assert!(settings.parent_walk.value);
+        assert_eq!(
+            settings.extra_search_dirs.value,
+            vec![dir.path().join("commands")]
+        );
+    }
+
+    #[test]
+    fn env_overrides() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("config.yml");
+        fs::write(&file, "shell: bash\nconfirm: false\n").unwrap();
+
+        let env = |name: &str| match name {
+            "ZXC_SHELL" => Some("zsh".to_string()),
+            "ZXC_CONFIRM" => Some("true".to_string()),
+            "ZXC_VERBOSITY" => Some("verbose".to_string()),
+            _ => None,
+        };
+        let settings = Settings::load(&[file], env).unwrap();
+        assert_eq!(settings.shell.value, "zsh");
+        assert_eq!(settings.shell.source, SettingSource::Env("ZXC_SHELL"));
+        assert!(settings.confirm.value);
+        assert_eq!(settings.verbosity.value, Verbosity::Verbose);
+    }
+
+    #[test]
+    fn invalid_env_value() {
+        let env = |name: &str| match name {
+            "ZXC_COLOR" => Some("sometimes".to_string()),
+            _ => None,
+        };
+        let result = Settings::load(&[], env);
+        assert!(result.is_err_and(
+            |e| matches!(e, ZxcError::Settings { source, .. } if source == "$ZXC_COLOR")
+        ));
+    }
+
+    #[test]
+    fn invalid_file() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("config.yml");
+        fs::write(&file, "unknown_setting: true\n").unwrap();
+
+        let result = Settings::load(std::slice::from_ref(&file), |_| None);
+        assert!(
+            result.is_err_and(|e| matches!(e, ZxcError::Parse { path, .. } if path == Some(file)))
+        );
+    }
+
+    #[test]
+    fn describe() {
+        let settings = Settings::load(&[], |_| None).unwrap();
+        let description = settings.describe();
+        assert!(description.contains("color: auto (default)\n"));
+        assert!(description.contains("discovery.extra_search_dirs: [] (default)\n"));
+    }
+}
@@ -0,0 +1,190 @@
+use std::io::Error;
+use std::process::{Child, ExitStatus};
+
+/// Guard forwarding signals to a child process group.
+///
+/// While it's alive, `SIGINT`, `SIGTERM` and `SIGHUP` received by `zxc` are passed
+/// to the process group of the child instead of terminating `zxc`.
+/// Child is started in its own process group, so signals reach all processes it spawns.
+/// If `zxc` controls the terminal, the child process group takes it over until it exits,
+/// so signals generated by the terminal (e.g., Ctrl-C) are delivered directly to it.
+///
+/// Signals received before the child is spawned are forwarded once it's started.
+/// Previous signal handlers are restored when dropped.
+pub struct SignalForwarder {
+    #[cfg(unix)]
+    previous_actions: Vec<(libc::c_int, libc::sigaction)>,
+    #[cfg(unix)]
+    terminal: Option<libc::c_int>,
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::sync::atomic::{AtomicI32, Ordering};
+
+    /// Signals forwarded to a child process group.
+    pub const FORWARDED_SIGNALS: [libc::c_int; 3] = [libc::SIGINT, libc::SIGTERM, libc::SIGHUP];
+
+    /// Process group of currently running child process, `0` if none.
+    pub static CHILD_PGID: AtomicI32 = AtomicI32::new(0);
+
+    /// Signal received before child process was spawned, `0` if none.
+    pub static PENDING_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+    /// Signal handler. Only async-signal-safe functions are allowed here.
+    pub extern "C" fn forward_signal(signal: libc::c_int) {
+        let child_pgid = CHILD_PGID.load(Ordering::SeqCst);
+        if child_pgid <= 0 {
+            PENDING_SIGNAL.store(signal, Ordering::SeqCst);
+            return;
+        }
+        unsafe {
+            libc::killpg(child_pgid, signal);
+        }
+    }
+
+    /// Find terminal controlled by process group of `zxc`.
+    pub fn find_terminal() -> Option<libc::c_int> {
+        [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO]
+            .into_iter()
+            .find(|&fd| unsafe { libc::isatty(fd) == 1 && libc::tcgetpgrp(fd) == libc::getpgrp() })
+    }
+
+    /// Make specified process group foreground one of the terminal.
+    /// `SIGTTOU` is blocked, so it succeeds for background process groups as well.
+    /// Only async-signal-safe functions are used, so it can be called in a forked child.
+    pub fn set_foreground(terminal: libc::c_int, pgid: libc::pid_t) {
+        unsafe {
+            let mut mask: libc::sigset_t = std::mem::zeroed();
+            let mut previous_mask: libc::sigset_t = std::mem::zeroed();
+            libc::sigemptyset(&mut mask);
+            libc::sigaddset(&mut mask, libc::SIGTTOU);
+            libc::pthread_sigmask(libc::SIG_BLOCK, &mask, &mut previous_mask);
+            libc::tcsetpgrp(terminal, pgid);
+            libc::pthread_sigmask(libc::SIG_SETMASK, &previous_mask, std::ptr::null_mut());
+        }
+    }
+}
+
+impl SignalForwarder {
+    /// Start forwarding signals to a child process, spawned afterwards.
+    #[cfg(unix)]
+    pub fn new() -> Self {
+        let mut previous_actions = Vec::new();
+        for signal in imp::FORWARDED_SIGNALS {
+            unsafe {
+                let mut action: libc::sigaction = std::mem::zeroed();
+                action.sa_sigaction = imp::forward_signal as *const () as libc::sighandler_t;
+                action.sa_flags = libc::SA_RESTART;
+                libc::sigemptyset(&mut action.sa_mask);
+
+                let mut previous_action: libc::sigaction = std::mem::zeroed();
+                if libc::sigaction(signal, &action, &mut previous_action) == 0 {
+                    previous_actions.push((signal, previous_action));
+                }
+            }
+        }
+
+        SignalForwarder {
+            previous_actions,
+            terminal: imp::find_terminal(),
+        }
+    }
+
+    /// Signal forwarding is not supported on this platform.
+    #[cfg(not(unix))]
+    pub fn new() -> Self {
+        SignalForwarder {}
+    }
+
+    /// Configure command to start in its own process group, controlling the terminal.
+    #[cfg(unix)]
+    pub fn prepare(&self, command: &mut std::process::Command) {
+        use std::os::unix::process::CommandExt;
+
+        command.process_group(0);
+        if let Some(terminal) = self.terminal {
+            // Terminal is taken over before running the command, so it can read input at once.
+            unsafe {
+                command.pre_exec(move || {
+                    imp::set_foreground(terminal, libc::getpid());
+                    Ok(())
+                });
+            }
+        }
+    }
+
+    /// Forward signals to spawned child process group and wait for the child to finish.
+    /// When child is stopped (e.g., by Ctrl-Z), `zxc` is stopped as well and resumes child
+    /// once continued.
+    #[cfg(unix)]
+    pub fn wait(&self, child: &mut Child) -> Result<ExitStatus, Error> {
+        use std::os::unix::process::ExitStatusExt;
+        use std::sync::atomic::Ordering;
+
+        let child_pid = child.id() as libc::pid_t;
+        imp::CHILD_PGID.store(child_pid, Ordering::SeqCst);
+        let pending_signal = imp::PENDING_SIGNAL.swap(0, Ordering::SeqCst);
+        if pending_signal != 0 {
+            unsafe {
+                libc::killpg(child_pid, pending_signal);
+            }
+        }
+
+        loop {
+            let mut status: libc::c_int = 0;
+            if unsafe { libc::waitpid(child_pid, &mut status, libc::WUNTRACED) } == -1 {
+                let error = Error::last_os_error();
+                if error.kind() == std::io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(error);
+            }
+            if !libc::WIFSTOPPED(status) {
+                if let Some(terminal) = self.terminal {
+                    imp::set_foreground(terminal, unsafe { libc::getpgrp() });
+                }
+                return Ok(ExitStatus::from_raw(status));
+            }
+
+            // Child is stopped - stop `zxc` as well, so its caller regains control.
+            unsafe {
+                if let Some(terminal) = self.terminal {
+                    imp::set_foreground(terminal, libc::getpgrp());
+                }
+                libc::kill(libc::getpid(), libc::WSTOPSIG(status));
+
+                // Continued - resume child, in foreground if `zxc` is in foreground.
+                if let Some(terminal) = self.terminal
+                    && libc::tcgetpgrp(terminal) == libc::getpgrp()
+                {
+                    imp::set_foreground(terminal, child_pid);
+                }
+                libc::killpg(child_pid, libc::SIGCONT);
+            }
+        }
+    }
+
+    /// Wait for the child to finish.
+    #[cfg(not(unix))]
+    pub fn wait(&self, child: &mut Child) -> Result<ExitStatus, Error> {
+        child.wait()
+    }
+}
+
+impl Drop for SignalForwarder {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        {
+            use std::sync::atomic::Ordering;
+
+            for (signal, previous_action) in &self.previous_actions {
+                unsafe {
+                    libc::sigaction(*signal, previous_action, std::ptr::null_mut());
+                }
+            }
+            imp::CHILD_PGID.store(0, Ordering::SeqCst);
+            imp::PENDING_SIGNAL.store(0, Ordering::SeqCst);
+        }
+    }
+}
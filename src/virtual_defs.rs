@@ -0,0 +1,310 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::def_importer::{import_makefile, import_package_json};
+use crate::error::ZxcError;
+use crate::yaml_command_defs::{YamlCommandDef, YamlCommandDefs};
+
+/// Name of directory configuration file.
+pub const DIRECTORY_CONFIG_NAME: &str = ".zxc.config.yml";
+
+/// Source of virtual command definitions.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum VirtualDefSource {
+    /// Scripts from `package.json`.
+    Npm,
+    /// Targets from `Makefile`.
+    Make,
+    /// Aliases from `.cargo/config.toml`.
+    Cargo,
+}
+
+impl VirtualDefSource {
+    /// Prefix of command names.
+    fn namespace(&self) -> &'static str {
+        match self {
+            VirtualDefSource::Npm => "npm",
+            VirtualDefSource::Make => "make",
+            VirtualDefSource::Cargo => "cargo",
+        }
+    }
+
+    /// Path to a file commands are taken from, relative to a directory.
+    fn relative_path(&self) -> PathBuf {
+        match self {
+            VirtualDefSource::Npm => PathBuf::from("package.json"),
+            VirtualDefSource::Make => PathBuf::from("Makefile"),
+            VirtualDefSource::Cargo => Path::new(".cargo").join("config.toml"),
+        }
+    }
+
+    /// Determine source based on file path.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let file_name = path.file_name()?.to_str()?;
+        let parent_name = path
+            .parent()
+            .and_then(|x| x.file_name())
+            .and_then(|x| x.to_str());
+        match (parent_name, file_name) {
+            (_, "package.json") => Some(VirtualDefSource::Npm),
+            (_, "Makefile") => Some(VirtualDefSource::Make),
+            (Some(".cargo"), "config.toml") => Some(VirtualDefSource::Cargo),
+            _ => None,
+        }
+    }
+}
+
+/// Directory configuration.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct DirectoryConfig {
+    /// Sources of virtual command definitions.
+    #[serde(default)]
+    pub virtual_definitions: Vec<VirtualDefSource>,
+}
+
+/// Load directory configuration from specified directory.
+/// Default configuration is returned if file doesn't exist.
+pub fn load_directory_config(directory_path: &Path) -> Result<DirectoryConfig, ZxcError> {
+    let config_path = directory_path.join(DIRECTORY_CONFIG_NAME);
+    if !config_path.exists() {
+        return Ok(DirectoryConfig::default());
+    }
+
+    let content = match fs::read_to_string(&config_path) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(ZxcError::Io {
+                path: Some(config_path),
+                source: e,
+            });
+        }
+    };
+    // Empty file is a valid configuration.
+    if content.trim().is_empty() {
+        return Ok(DirectoryConfig::default());
+    }
+    match serde_yaml::from_str(&content) {
+        Ok(x) => Ok(x),
+        Err(e) => Err(ZxcError::from_yaml_error(&content, e).with_path(&config_path)),
+    }
+}
+
+/// Find files of enabled virtual definition sources in specified directory.
+pub fn find_virtual_def_files(directory_path: &Path, sources: &[VirtualDefSource]) -> Vec<PathBuf> {
+    sources
+        .iter()
+        .map(|x| directory_path.join(x.relative_path()))
+        .filter(|x| x.is_file())
+        .collect()
+}
+
+/// Create virtual command definition.
+fn virtual_def(command: String, description: Option<String>) -> YamlCommandDef {
+    YamlCommandDef {
+        command,
+        description,
+        ..Default::default()
+    }
+}
+
+/// Load aliases from `.cargo/config.toml` content.
+fn load_cargo_aliases(content: &str) -> Result<BTreeMap<String, String>, ZxcError> {
+    let config: toml::Table = match toml::from_str(content) {
+        Ok(x) => x,
+        Err(e) => return Err(ZxcError::from_toml_error(content, e)),
+    };
+
+    let mut aliases = BTreeMap::new();
+    let Some(alias_table) = config.get("alias").and_then(|x| x.as_table()) else {
+        return Ok(aliases);
+    };
+    for (name, value) in alias_table {
+        // Aliases can be defined as a string or as a list of arguments.
+        let expansion = match value {
+            toml::Value::String(x) => x.clone(),
+            toml::Value::Array(x) => x
+                .iter()
+                .filter_map(|x| x.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            _ => continue,
+        };
+        aliases.insert(name.clone(), expansion);
+    }
+    Ok(aliases)
+}
+
+/// Load virtual command definitions from a string.
+/// Commands are run using native tool and namespaced with tool name, e.g., `npm:build`.
+pub fn load_virtual_defs_from_str(
+    content: &str,
+    source: VirtualDefSource,
+) -> Result<YamlCommandDefs, ZxcError> {
+    let defs: Vec<(String, YamlCommandDef)> = match source {
+        VirtualDefSource::Npm => import_package_json(content)?
+            .defs
+            .into_iter()
+            .map(|(name, def)| {
+                let def = virtual_def(format!("npm run {name}"), def.description);
+                (name, def)
+            })
+            .collect(),
+        VirtualDefSource::Make => import_makefile(content)
+            .defs
+            .into_iter()
+            .map(|(name, def)| {
+                let def = virtual_def(format!("make {name}"), def.description);
+                (name, def)
+            })
+            .collect(),
+        VirtualDefSource::Cargo => load_cargo_aliases(content)?
+            .into_iter()
+            .map(|(name, expansion)| {
+                let description = format!("Run `cargo {expansion}`.");
+                let def = virtual_def(format!("cargo {name}"), Some(description));
+                (name, def)
+            })
+            .collect(),
+    };
+
+    Ok(defs
+        .into_iter()
+        .map(|(name, def)| (format!("{}:{name}", source.namespace()), def))
+        .collect())
+}
+
+/// Load virtual command definitions from a file.
+pub fn load_virtual_defs_from_file(
+    file_path: PathBuf,
+    source: VirtualDefSource,
+) -> Result<YamlCommandDefs, ZxcError> {
+    let content = match fs::read_to_string(&file_path) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(ZxcError::Io {
+                path: Some(file_path),
+                source: e,
+            });
+        }
+    };
+    load_virtual_defs_from_str(&content, source).map_err(|e| e.with_path(&file_path))
+}
+
+#[cfg(test)]
+mod virtual_def_source_tests {
+    use super::VirtualDefSource;
+    use std::path::Path;
+
+    #[test]
+    fn from_path() {
+        assert_eq!(
+            VirtualDefSource::from_path(Path::new("/opt/app/package.json")),
+            Some(VirtualDefSource::Npm)
+        );
+        assert_eq!(
+            VirtualDefSource::from_path(Path::new("/opt/app/Makefile")),
+            Some(VirtualDefSource::Make)
+        );
+        assert_eq!(
+            VirtualDefSource::from_path(Path::new("/opt/app/.cargo/config.toml")),
+            Some(VirtualDefSource::Cargo)
+        );
+        assert_eq!(
+            VirtualDefSource::from_path(Path::new("/opt/app/config.toml")),
+            None
+        );
+        assert_eq!(
+            VirtualDefSource::from_path(Path::new("/opt/app/.zxc.json")),
+            None
+        );
+    }
+}
+
+#[cfg(test)]
+mod load_directory_config_tests {
+    use super::{DIRECTORY_CONFIG_NAME, VirtualDefSource, load_directory_config};
+    use crate::error::ZxcError;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn missing_file() {
+        let dir = tempdir().unwrap();
+        let config = load_directory_config(dir.path()).unwrap();
+        assert!(config.virtual_definitions.is_empty());
+    }
+
+    #[test]
+    fn valid_file() {
+        let dir = tempdir().unwrap();
+        let content = "virtual_definitions: [npm, cargo]\n";
+        fs::write(dir.path().join(DIRECTORY_CONFIG_NAME), content).unwrap();
+
+        let config = load_directory_config(dir.path()).unwrap();
+        assert_eq!(
+            config.virtual_definitions,
+            vec![VirtualDefSource::Npm, VirtualDefSource::Cargo]
+        );
+    }
+
+    #[test]
+    fn unknown_source() {
+        let dir = tempdir().unwrap();
+        let content = "virtual_definitions: [gradle]\n";
+        fs::write(dir.path().join(DIRECTORY_CONFIG_NAME), content).unwrap();
+
+        let result = load_directory_config(dir.path());
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Parse { path: Some(_), .. })));
+    }
+}
+
+#[cfg(test)]
+mod load_virtual_defs_from_str_tests {
+    use super::{VirtualDefSource, load_virtual_defs_from_str};
+
+    #[test]
+    fn npm() {
+        let content = r#"{ "scripts": { "build": "tsc", "test": "jest" } }"#;
+
+        let defs = load_virtual_defs_from_str(content, VirtualDefSource::Npm).unwrap();
+        assert_eq!(defs.len(), 2);
+        assert_eq!(defs.get("npm:build").unwrap().command, "npm run build");
+        assert_eq!(defs.get("npm:test").unwrap().command, "npm run test");
+    }
+
+    #[test]
+    fn make() {
+        let content = "# Build the project.\nbuild:\n\tcc -o app main.c\n\ninstall: build\n";
+
+        let defs = load_virtual_defs_from_str(content, VirtualDefSource::Make).unwrap();
+        let build = defs.get("make:build").unwrap();
+        assert_eq!(build.command, "make build");
+        assert_eq!(build.description.as_deref(), Some("Build the project."));
+        assert_eq!(defs.get("make:install").unwrap().command, "make install");
+    }
+
+    #[test]
+    fn cargo() {
+        let content = r#"
+[alias]
+b = "build"
+rr = ["run", "--release"]
+
+[build]
+jobs = 4
+"#;
+
+        let defs = load_virtual_defs_from_str(content, VirtualDefSource::Cargo).unwrap();
+        assert_eq!(defs.len(), 2);
+        assert_eq!(defs.get("cargo:b").unwrap().command, "cargo b");
+        assert_eq!(
+            defs.get("cargo:rr").unwrap().description.as_deref(),
+            Some("Run `cargo run --release`.")
+        );
+    }
+}
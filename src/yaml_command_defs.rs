@@ -1,65 +1,588 @@
 use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
-use std::io::{Error, ErrorKind};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{SourceSnippet, ZxcError};
+
+/// YAML-faced default value of an argument.
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug, PartialEq)]
+#[serde(untagged, deny_unknown_fields)]
+#[schemars(description = "Default value of an argument - a value or output of a shell command.")]
+pub enum YamlDefaultValue {
+    /// Value. Can reference other arguments, e.g., `{{ name }}-svc`.
+    Value(String),
+    /// Output of a shell command, e.g., `{ sh: "git rev-parse --short HEAD" }`.
+    Shell {
+        /// Shell command. Can reference other arguments.
+        sh: String,
+    },
+}
+
+/// YAML-faced allowed values of an argument.
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug, PartialEq)]
+#[serde(untagged, deny_unknown_fields)]
+#[schemars(
+    description = "Allowed values of an argument - a list of values or output of a shell command."
+)]
+pub enum YamlChoices {
+    /// List of values.
+    Values(Vec<String>),
+    /// Lines of a shell command output, e.g., `{ sh: "docker compose config --services" }`.
+    Shell {
+        /// Shell command. Can reference other arguments.
+        sh: String,
+        /// How long output is cached. E.g., `30s`, `10m`. Not cached by default.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        cache: Option<String>,
+    },
+}
 
 /// YAML-faced definition of an argument.
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug, Default)]
 #[serde(deny_unknown_fields)]
+#[schemars(description = "Argument of a command - named, using flags, or positional.")]
 pub struct YamlArgumentDef {
-    /// Flags.
+    /// Flags. Can be omitted only when overriding existing argument.
+    /// First short and long flags are primary, others are shown as aliases.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub flags: Vec<String>,
-    /// Default value.
-    pub default: Option<String>,
+    /// Flags accepted, but not shown in help. E.g., deprecated names.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hidden_flags: Vec<String>,
+    /// Default value. Evaluated only if argument is not provided.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<YamlDefaultValue>,
     /// Description of an argument.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Environment variable providing value if argument is not provided in command line.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<String>,
+    /// Allowed values. Value is selected interactively if argument is not provided.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub choices: Option<YamlChoices>,
+    /// Regular expression whole value must match.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// Minimum value. Value must be a number.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    /// Maximum value. Value must be a number.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+}
+
+/// YAML-faced definition of a retry policy.
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug, Default)]
+#[serde(deny_unknown_fields)]
+#[schemars(description = "Policy of retrying a failed command.")]
+pub struct YamlRetryDef {
+    /// Maximum number of attempts, including the first one.
+    pub attempts: u32,
+    /// Delay between attempts. E.g., `500ms`, `2s`, `1m`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delay: Option<String>,
+    /// Backoff strategy - `constant` or `exponential`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backoff: Option<String>,
+    /// Exit codes for which command is retried. All non-zero codes if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_exit_codes: Option<Vec<i32>>,
+}
+
+/// YAML-faced definition of a command usage example.
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+#[schemars(description = "Usage example of a command.")]
+pub struct YamlExampleDef {
+    /// Example invocation. E.g., `zxc deploy --env production`.
+    pub command: String,
+    /// Explanation of an example.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
 }
 
+/// Scope of a command definition.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DefinitionScope {
+    /// Defined for current directory - locally or externally.
+    #[default]
+    Directory,
+    /// Defined in global definition file, available in every directory.
+    Global,
+}
+
+/// Location of a command definition.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DefinitionOrigin {
+    /// Path to definition file.
+    pub path: PathBuf,
+    /// Line number, starting from 1, if known.
+    pub line: Option<usize>,
+}
+
+impl fmt::Display for DefinitionOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{line}", self.path.display()),
+            None => write!(f, "{}", self.path.display()),
+        }
+    }
+}
+
 /// YAML-faced definition of a command.
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug, Default)]
 #[serde(deny_unknown_fields)]
+#[schemars(description = "Command run by `zxc`.")]
 pub struct YamlCommandDef {
-    /// Shell command.
+    /// Shell command. Can be omitted only when overriding or extending existing command.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub command: String,
-    /// Command description.
+    /// Command description. First line is shown in commands list.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// Detailed help, shown by `zxc <command> --help`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help: Option<String>,
+    /// Usage examples, shown by `zxc <command> --help`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub examples: Option<Vec<YamlExampleDef>>,
+    /// Help section command is listed in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Don't show shell command in help, e.g., for long scripts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hide_command: Option<bool>,
     /// Arguments used by the command.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub arguments: Option<BTreeMap<String, YamlArgumentDef>>,
+    /// Groups of arguments which cannot be used together.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflicts: Option<Vec<Vec<String>>>,
+    /// Arguments required by an argument, if it's used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requires: Option<BTreeMap<String, Vec<String>>>,
+    /// Groups of arguments from which exactly one must be used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub one_of: Option<Vec<Vec<String>>>,
+    /// Retry policy.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry: Option<YamlRetryDef>,
+    /// Environment variables set for the command.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<BTreeMap<String, String>>,
+    /// Shell used to run the command, overrides `shell` setting.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
+    /// Name of command this command is based on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    /// Command is runnable, but not listed in help.
+    /// Hidden command without `command` is abstract - used only as a base for other commands.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hidden: Option<bool>,
+    /// Command can be run only by other commands, not directly.
+    /// Private command without `command` is abstract - used only as a base for other commands.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub private: Option<bool>,
+    /// Deprecation message - warning is printed when command is run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<String>,
+    /// Scope - determined by definition file, not part of it.
+    #[serde(skip)]
+    pub scope: DefinitionScope,
+    /// Location - determined by definition file, not part of it.
+    #[serde(skip)]
+    pub origin: Option<DefinitionOrigin>,
+    /// Locations of overridden definitions, most recent first.
+    #[serde(skip)]
+    pub shadowed: Vec<DefinitionOrigin>,
+    /// Command is removed - defined as `null` in definition file.
+    #[serde(skip)]
+    pub removed: bool,
+    /// Command patches definition with lower precedence instead of replacing it -
+    /// determined by definition file, not part of it.
+    #[serde(skip)]
+    pub overlay: bool,
+}
+
+impl YamlCommandDef {
+    /// Check if command is abstract - private or hidden and without `command`.
+    pub fn is_abstract(&self) -> bool {
+        (self.private == Some(true) || self.hidden == Some(true)) && self.command.is_empty()
+    }
 }
 
 /// Available command definitions.
 pub type YamlCommandDefs = BTreeMap<String, YamlCommandDef>;
 
+/// Content of definition file - `null` removes command defined by previous files.
+pub type YamlDefinitionFile = BTreeMap<String, Option<YamlCommandDef>>;
+
+/// Convert definition file content to command definitions.
+/// Commands defined as `null` are marked as removed.
+pub fn from_definition_file(definition_file: YamlDefinitionFile) -> YamlCommandDefs {
+    definition_file
+        .into_iter()
+        .map(|(name, def)| {
+            let def = def.unwrap_or_else(|| YamlCommandDef {
+                removed: true,
+                ..Default::default()
+            });
+            (name, def)
+        })
+        .collect()
+}
+
+/// Check if line of definition file content starts with specified key.
+/// YAML, TOML and JSON keys are recognized.
+fn is_key_line(line: &str, key: &str) -> bool {
+    let prefixes = [
+        format!("{key}:"),
+        format!("\"{key}\":"),
+        format!("'{key}':"),
+        format!("[{key}]"),
+        format!("[\"{key}\"]"),
+        format!("{key} ="),
+        format!("\"{key}\" ="),
+    ];
+    let trimmed = line.trim_start();
+    prefixes.iter().any(|x| trimmed.starts_with(x.as_str()))
+}
+
+/// Indentation of a line.
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Find line of a top-level key in definition file content.
+/// Least indented occurrence is used.
+fn find_key_line(content: &str, key: &str) -> Option<usize> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| is_key_line(line, key))
+        .min_by_key(|(_, line)| indentation(line))
+        .map(|(index, _)| index + 1)
+}
+
+/// Range of line indices of a definition starting at specified line index.
+/// Definition ends before the first non-empty line not indented more than its first line.
+fn definition_lines(lines: &[&str], line_index: usize) -> std::ops::Range<usize> {
+    let base_indentation = indentation(lines[line_index]);
+    let end = lines[line_index + 1..]
+        .iter()
+        .position(|x| !x.trim().is_empty() && indentation(x) <= base_indentation)
+        .map_or(lines.len(), |x| line_index + 1 + x);
+    line_index..end
+}
+
+/// Find snippet of a command definition in its definition file.
+/// Nested keys are looked up in specified order, e.g., `["arguments", "name"]`,
+/// the last key found is highlighted. Command key is highlighted if none of them is found.
+pub fn find_definition_snippet(origin: &DefinitionOrigin, keys: &[&str]) -> Option<SourceSnippet> {
+    let content = fs::read_to_string(&origin.path).ok()?;
+    let lines: Vec<&str> = content.lines().collect();
+    let mut line_index = origin.line?.checked_sub(1)?;
+    lines.get(line_index)?;
+
+    // Step into nested keys.
+    for key in keys {
+        let nested = definition_lines(&lines, line_index)
+            .skip(1)
+            .filter(|x| is_key_line(lines[*x], key))
+            .min_by_key(|x| indentation(lines[*x]));
+        match nested {
+            Some(x) => line_index = x,
+            None => break,
+        }
+    }
+
+    let line = lines[line_index];
+    let key = line.trim_start().split(':').next().unwrap_or_default();
+    SourceSnippet::new(
+        Some(&origin.path),
+        &content,
+        line_index + 1,
+        indentation(line) + 1,
+        key.chars().count(),
+    )
+}
+
+/// Map snippet of a template into definition file of the command it belongs to.
+/// Returns `None` if the template line is not found in the command definition,
+/// e.g., because of escape sequences.
+pub fn find_template_snippet(
+    origin: &DefinitionOrigin,
+    template_snippet: &SourceSnippet,
+) -> Option<SourceSnippet> {
+    if template_snippet.line_content.trim().is_empty() {
+        return None;
+    }
+    let content = fs::read_to_string(&origin.path).ok()?;
+    let lines: Vec<&str> = content.lines().collect();
+    let line_index = origin.line?.checked_sub(1)?;
+    lines.get(line_index)?;
+
+    definition_lines(&lines, line_index).find_map(|x| {
+        let offset = lines[x].find(&template_snippet.line_content)?;
+        SourceSnippet::new(
+            Some(&origin.path),
+            &content,
+            x + 1,
+            lines[x][..offset].chars().count() + template_snippet.column,
+            template_snippet.length,
+        )
+    })
+}
+
+/// Record location of each command definition loaded from a file.
+pub fn set_definition_origins(
+    command_defs: &mut YamlCommandDefs,
+    definition_file_path: &Path,
+    definition_file_content: &str,
+) {
+    for (name, def) in command_defs.iter_mut() {
+        def.origin = Some(DefinitionOrigin {
+            path: definition_file_path.to_path_buf(),
+            line: find_key_line(definition_file_content, name),
+        });
+    }
+}
+
 /// Load YAML command definitions from a string.
-pub fn load_yaml_defs_from_str(definition_file_content: &str) -> Result<YamlCommandDefs, Error> {
-    match serde_yaml::from_str(definition_file_content) {
-        Ok(commands) => Ok(commands),
-        Err(e) => Err(Error::new(ErrorKind::InvalidData, e)),
+/// Anchors, aliases and merge keys (`<<`) are supported.
+pub fn load_yaml_defs_from_str(definition_file_content: &str) -> Result<YamlCommandDefs, ZxcError> {
+    // Apply merge keys.
+    let mut value: serde_yaml::Value = match serde_yaml::from_str(definition_file_content) {
+        Ok(x) => x,
+        Err(e) => return Err(ZxcError::from_yaml_error(definition_file_content, e)),
+    };
+    let original_value = value.clone();
+    if let Err(e) = value.apply_merge() {
+        return Err(ZxcError::from_yaml_error(definition_file_content, e));
+    }
+
+    // Content is parsed directly if no merge keys are used, so error locations are known.
+    let commands = match value == original_value {
+        true => serde_yaml::from_str(definition_file_content),
+        false => serde_yaml::from_value(value),
+    };
+    match commands {
+        Ok(commands) => Ok(from_definition_file(commands)),
+        Err(e) => Err(ZxcError::from_yaml_error(definition_file_content, e)),
     }
 }
 
 /// Load command definitions from a file.
-pub fn load_yaml_defs_from_file(definition_file_path: PathBuf) -> Result<YamlCommandDefs, Error> {
-    let definition_file_content = fs::read_to_string(definition_file_path)?;
-    load_yaml_defs_from_str(&definition_file_content)
+pub fn load_yaml_defs_from_file(
+    definition_file_path: PathBuf,
+) -> Result<YamlCommandDefs, ZxcError> {
+    let definition_file_content = match fs::read_to_string(&definition_file_path) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(ZxcError::Io {
+                path: Some(definition_file_path),
+                source: e,
+            });
+        }
+    };
+    let mut command_defs = load_yaml_defs_from_str(&definition_file_content)
+        .map_err(|e| e.with_path(&definition_file_path))?;
+    set_definition_origins(
+        &mut command_defs,
+        &definition_file_path,
+        &definition_file_content,
+    );
+    Ok(command_defs)
+}
+
+/// Apply overlay definition on top of base definition.
+/// - Fields other than `arguments` and `env` are replaced if set in overlay.
+/// - Arguments are merged by name, argument fields are replaced if set in overlay.
+/// - Environment variables are merged by name.
+///
+/// Location and scope are taken from overlay.
+fn patch_yaml_def(base: YamlCommandDef, overlay: YamlCommandDef) -> YamlCommandDef {
+    let mut arguments = base.arguments.unwrap_or_default();
+    for (name, overlay_argument) in overlay.arguments.into_iter().flatten() {
+        let argument = arguments.entry(name).or_default();
+        if !overlay_argument.flags.is_empty() {
+            argument.flags = overlay_argument.flags;
+        }
+        if !overlay_argument.hidden_flags.is_empty() {
+            argument.hidden_flags = overlay_argument.hidden_flags;
+        }
+        if overlay_argument.default.is_some() {
+            argument.default = overlay_argument.default;
+        }
+        if overlay_argument.description.is_some() {
+            argument.description = overlay_argument.description;
+        }
+        if overlay_argument.env.is_some() {
+            argument.env = overlay_argument.env;
+        }
+        if overlay_argument.choices.is_some() {
+            argument.choices = overlay_argument.choices;
+        }
+        if overlay_argument.pattern.is_some() {
+            argument.pattern = overlay_argument.pattern;
+        }
+        if overlay_argument.min.is_some() {
+            argument.min = overlay_argument.min;
+        }
+        if overlay_argument.max.is_some() {
+            argument.max = overlay_argument.max;
+        }
+    }
+
+    let mut env = base.env.unwrap_or_default();
+    env.extend(overlay.env.into_iter().flatten());
+
+    YamlCommandDef {
+        command: match overlay.command.is_empty() {
+            true => base.command,
+            false => overlay.command,
+        },
+        description: overlay.description.or(base.description),
+        help: overlay.help.or(base.help),
+        examples: overlay.examples.or(base.examples),
+        group: overlay.group.or(base.group),
+        hide_command: overlay.hide_command.or(base.hide_command),
+        arguments: (!arguments.is_empty()).then_some(arguments),
+        conflicts: overlay.conflicts.or(base.conflicts),
+        requires: overlay.requires.or(base.requires),
+        one_of: overlay.one_of.or(base.one_of),
+        retry: overlay.retry.or(base.retry),
+        env: (!env.is_empty()).then_some(env),
+        shell: overlay.shell.or(base.shell),
+        extends: overlay.extends.or(base.extends),
+        hidden: overlay.hidden.or(base.hidden),
+        private: overlay.private.or(base.private),
+        deprecated: overlay.deprecated.or(base.deprecated),
+        scope: overlay.scope,
+        origin: overlay.origin,
+        shadowed: overlay.shadowed,
+        removed: overlay.removed,
+        overlay: overlay.overlay,
+    }
+}
+
+/// Resolve `extends` of specified command.
+/// `chain` contains names of commands extending resolved one, used to detect cycles.
+fn resolve_extended_def(
+    command_defs: &YamlCommandDefs,
+    name: &str,
+    chain: &mut Vec<String>,
+) -> Result<YamlCommandDef, ZxcError> {
+    let def = command_defs[name].clone();
+    let Some(base_name) = def.extends.clone() else {
+        return Ok(def);
+    };
+
+    chain.push(name.to_string());
+    let validation_error = |message: String| ZxcError::Validation {
+        command: chain[0].clone(),
+        origin: command_defs[&chain[0]].origin.clone(),
+        message,
+        snippet: None,
+    };
+    if chain.contains(&base_name) {
+        return Err(validation_error(format!(
+            "cyclic 'extends': {} -> {base_name}",
+            chain.join(" -> ")
+        )));
+    }
+    if command_defs.get(&base_name).is_none_or(|x| x.removed) {
+        return Err(validation_error(format!(
+            "'extends' refers to unknown command '{base_name}'"
+        )));
+    }
+
+    // Visibility and deprecation are not inherited.
+    let (hidden, private, deprecated) = (def.hidden, def.private, def.deprecated.clone());
+    let base = resolve_extended_def(command_defs, &base_name, chain)?;
+    let mut def = patch_yaml_def(base, def);
+    def.extends = None;
+    def.hidden = hidden;
+    def.private = private;
+    def.deprecated = deprecated;
+    Ok(def)
+}
+
+/// Resolve `extends` of all commands.
+pub fn resolve_extends(command_defs: YamlCommandDefs) -> Result<YamlCommandDefs, ZxcError> {
+    let mut resolved_defs = YamlCommandDefs::new();
+    for name in command_defs.keys() {
+        let def = resolve_extended_def(&command_defs, name, &mut vec![])?;
+        resolved_defs.insert(name.clone(), def);
+    }
+    Ok(resolved_defs)
 }
 
 /// Merge YAML command definitions.
+/// Later definitions replace earlier ones, overlay definitions are applied on top of them
+/// (see `patch_yaml_def`). Locations of overridden ones are kept as shadowed.
+/// Commands marked as removed are dropped.
 pub fn merge_yaml_defs(command_defs: Vec<YamlCommandDefs>) -> YamlCommandDefs {
     let mut merged_defs = YamlCommandDefs::new();
-    for mut def in command_defs {
-        merged_defs.append(&mut def);
+    for defs in command_defs {
+        for (name, def) in defs {
+            if def.removed {
+                merged_defs.remove(&name);
+                continue;
+            }
+
+            let def = match merged_defs.remove(&name) {
+                Some(overridden) => {
+                    let mut shadowed: Vec<DefinitionOrigin> =
+                        overridden.origin.iter().cloned().collect();
+                    shadowed.extend(overridden.shadowed.iter().cloned());
+                    let mut def = match def.overlay {
+                        true => patch_yaml_def(overridden, def),
+                        false => def,
+                    };
+                    def.shadowed = shadowed;
+                    def
+                }
+                None => def,
+            };
+            merged_defs.insert(name, def);
+        }
     }
     merged_defs
 }
 
+/// Describe overridden command definitions.
+/// Returns warning messages.
+pub fn find_override_warnings(command_defs: &YamlCommandDefs) -> Vec<String> {
+    let unknown = "unknown location".to_string();
+    command_defs
+        .iter()
+        .filter(|(_, def)| !def.shadowed.is_empty())
+        .map(|(name, def)| {
+            let origin = def
+                .origin
+                .as_ref()
+                .map_or(unknown.clone(), |x| x.to_string());
+            let shadowed: Vec<String> = def.shadowed.iter().map(|x| format!("'{x}'")).collect();
+            format!(
+                "command '{name}' from '{origin}' overrides definition from {}",
+                shadowed.join(", ")
+            )
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod load_yaml_defs_from_str_tests {
-    use std::io::ErrorKind;
-
     use super::load_yaml_defs_from_str;
+    use crate::error::ZxcError;
+    use crate::zxc_command_defs::load_zxc_defs;
 
     #[test]
     fn valid_yaml() {
@@ -94,6 +617,29 @@ mod load_yaml_defs_from_str_tests {
         assert!(test_def.arguments.is_none());
     }
 
+    #[test]
+    fn retry_policy() {
+        let yaml_content = r#"
+          fetch:
+            command: curl https://example.com
+            retry:
+              attempts: 3
+              delay: 2s
+              backoff: exponential
+              on_exit_codes: [1, 75]
+        "#;
+
+        let result = load_yaml_defs_from_str(yaml_content);
+        assert!(result.is_ok());
+
+        let defs = result.unwrap();
+        let retry = defs.get("fetch").unwrap().retry.as_ref().unwrap();
+        assert_eq!(retry.attempts, 3);
+        assert_eq!(retry.delay.as_deref(), Some("2s"));
+        assert_eq!(retry.backoff.as_deref(), Some("exponential"));
+        assert_eq!(retry.on_exit_codes, Some(vec![1, 75]));
+    }
+
     #[test]
     fn invalid_yaml() {
         let yaml_content = r#"
@@ -101,18 +647,69 @@ mod load_yaml_defs_from_str_tests {
         "#;
 
         let result = load_yaml_defs_from_str(yaml_content);
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+        assert!(result.is_err_and(|e| matches!(
+            e,
+            ZxcError::Parse {
+                snippet: Some(_),
+                ..
+            }
+        )));
     }
 
     #[test]
-    fn missing_mandatory_field() {
+    fn merge_keys() {
         let yaml_content = r#"
-          name:
-            description: Some description.
+          .base: &base
+            hidden: true
+            env:
+              RUST_LOG: debug
+          build:
+            <<: *base
+            command: cargo build
+          test:
+            <<: *base
+            command: cargo test
+            hidden: false
         "#;
+
+        let defs = load_yaml_defs_from_str(yaml_content).unwrap();
+        let build = defs.get("build").unwrap();
+        assert_eq!(build.command, "cargo build");
+        assert_eq!(build.hidden, Some(true));
+        assert_eq!(build.env.as_ref().unwrap()["RUST_LOG"], "debug");
+        assert_eq!(defs.get("test").unwrap().hidden, Some(false));
+        assert!(defs.get(".base").unwrap().is_abstract());
+
+        let zxc_defs = load_zxc_defs(defs).unwrap();
+        let names: Vec<&str> = zxc_defs.iter().map(|x| x.name.as_str()).collect();
+        assert_eq!(names, vec!["build", "test"]);
+    }
+
+    #[test]
+    fn unknown_field_with_merge_keys() {
+        let yaml_content = r#"
+          .base: &base
+            command: echo
+          build:
+            <<: *base
+            some_unknown_field: asdf
+        "#;
+
         let result = load_yaml_defs_from_str(yaml_content);
-        assert!(result.is_err_and(|e| e.kind() == ErrorKind::InvalidData));
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Parse { .. })));
+    }
+
+    #[test]
+    fn removed_command() {
+        let yaml_content = r#"
+          run: null
+          test:
+            command: cargo test
+        "#;
+
+        let defs = load_yaml_defs_from_str(yaml_content).unwrap();
+        assert!(defs.get("run").unwrap().removed);
+        assert!(!defs.get("test").unwrap().removed);
     }
 
     #[test]
@@ -123,7 +720,7 @@ mod load_yaml_defs_from_str_tests {
             some_unknown_field: asdf
         "#;
         let result = load_yaml_defs_from_str(yaml_content);
-        assert!(result.is_err_and(|e| e.kind() == ErrorKind::InvalidData));
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Parse { .. })));
     }
 
     #[test]
@@ -136,13 +733,14 @@ mod load_yaml_defs_from_str_tests {
                 some_unknown_field: asdf
         "#;
         let result = load_yaml_defs_from_str(yaml_content);
-        assert!(result.is_err_and(|e| e.kind() == ErrorKind::InvalidData));
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Parse { .. })));
     }
 }
 
 #[cfg(test)]
 mod load_yaml_defs_from_file_tests {
     use super::load_yaml_defs_from_file;
+    use crate::error::ZxcError;
     use std::fs::File;
     use std::io::{ErrorKind, Write};
     use std::path::PathBuf;
@@ -163,12 +761,21 @@ mod load_yaml_defs_from_file_tests {
         let mut file = File::create(&file_path).unwrap();
         file.write_all(yaml_content.as_bytes()).unwrap();
 
-        let result = load_yaml_defs_from_file(file_path);
+        let result = load_yaml_defs_from_file(file_path.clone());
         assert!(result.is_ok());
 
         let defs = result.unwrap();
         assert!(defs.contains_key("run"));
         assert_eq!(defs.get("run").unwrap().command, "cargo run");
+        assert_eq!(
+            defs.get("run")
+                .unwrap()
+                .origin
+                .as_ref()
+                .unwrap()
+                .to_string(),
+            format!("{}:2", file_path.display())
+        );
     }
 
     #[test]
@@ -176,14 +783,245 @@ mod load_yaml_defs_from_file_tests {
         let missing_file_path = PathBuf::from("non_existent.yaml");
 
         let result = load_yaml_defs_from_file(missing_file_path);
-        assert!(result.is_err_and(|e| e.kind() == ErrorKind::NotFound));
+        assert!(result.is_err_and(
+            |e| matches!(e, ZxcError::Io { source, .. } if source.kind() == ErrorKind::NotFound)
+        ));
+    }
+
+    #[test]
+    fn invalid_file() {
+        let yaml_content = "run:\n  command: [\n";
+
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("commands.yaml");
+
+        let mut file = File::create(&file_path).unwrap();
+        file.write_all(yaml_content.as_bytes()).unwrap();
+
+        let result = load_yaml_defs_from_file(file_path.clone());
+        assert!(
+            result.is_err_and(
+                |e| matches!(e, ZxcError::Parse { path, .. } if path == Some(file_path))
+            )
+        );
+    }
+}
+
+#[cfg(test)]
+mod find_key_line_tests {
+    use super::find_key_line;
+
+    #[test]
+    fn yaml() {
+        let content = "name:\n  command: echo\n  arguments:\n    run: {}\nrun:\n  command: ls\n";
+        assert_eq!(find_key_line(content, "name"), Some(1));
+        assert_eq!(find_key_line(content, "run"), Some(5));
+        assert_eq!(find_key_line(content, "missing"), None);
+    }
+
+    #[test]
+    fn toml() {
+        let content = "[greet.arguments.name]\nflags = []\n\n[greet]\ncommand = \"echo\"\n";
+        assert_eq!(find_key_line(content, "greet"), Some(4));
+    }
+
+    #[test]
+    fn json() {
+        let content = "{\n  \"greet\": {\n    \"command\": \"echo\"\n  }\n}\n";
+        assert_eq!(find_key_line(content, "greet"), Some(2));
+    }
+}
+
+#[cfg(test)]
+mod find_definition_snippet_tests {
+    use super::{DefinitionOrigin, find_definition_snippet, find_template_snippet};
+    use crate::error::SourceSnippet;
+    use std::fs;
+    use tempfile::tempdir;
+
+    const CONTENT: &str = r#"
+test:
+  command: cargo test
+build:
+  command: echo {{ mode }}
+  arguments:
+    mode:
+      flags: [--mode]
+"#;
+
+    #[test]
+    fn nested_key() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join(".zxc.yml");
+        fs::write(&path, CONTENT).unwrap();
+        let origin = DefinitionOrigin {
+            path: path.clone(),
+            line: Some(4),
+        };
+
+        let snippet = find_definition_snippet(&origin, &["arguments", "mode"]).unwrap();
+        assert_eq!(snippet.path, Some(path));
+        assert_eq!((snippet.line, snippet.column, snippet.length), (7, 5, 4));
+
+        // Command key is used if nested key is not found.
+        let snippet = find_definition_snippet(&origin, &["retry"]).unwrap();
+        assert_eq!((snippet.line, snippet.column, snippet.length), (4, 1, 5));
+    }
+
+    #[test]
+    fn template() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join(".zxc.yml");
+        fs::write(&path, CONTENT).unwrap();
+        let origin = DefinitionOrigin {
+            path: path.clone(),
+            line: Some(4),
+        };
+
+        let template_snippet = SourceSnippet::new(None, "echo {{ mode }}", 1, 9, 4).unwrap();
+        let snippet = find_template_snippet(&origin, &template_snippet).unwrap();
+        assert_eq!(snippet.path, Some(path));
+        assert_eq!((snippet.line, snippet.column, snippet.length), (5, 20, 4));
+
+        // Template of another command is not used.
+        let template_snippet = SourceSnippet::new(None, "cargo test", 1, 1, 5).unwrap();
+        assert!(find_template_snippet(&origin, &template_snippet).is_none());
+    }
+}
+
+#[cfg(test)]
+mod resolve_extends_tests {
+    use super::{YamlDefaultValue, load_yaml_defs_from_str, resolve_extends};
+    use crate::error::ZxcError;
+
+    #[test]
+    fn extended_command() {
+        let yaml_content = r#"
+          deploy:
+            command: ./deploy.sh {{ env }}
+            description: Deploy.
+            arguments:
+              env:
+                flags: [--env]
+                default: staging
+          deploy-prod:
+            extends: deploy
+            arguments:
+              env:
+                default: production
+        "#;
+        let defs = load_yaml_defs_from_str(yaml_content).unwrap();
+
+        let defs = resolve_extends(defs).unwrap();
+        let deploy_prod = defs.get("deploy-prod").unwrap();
+        assert_eq!(deploy_prod.command, "./deploy.sh {{ env }}");
+        assert_eq!(deploy_prod.description.as_deref(), Some("Deploy."));
+        assert!(deploy_prod.extends.is_none());
+        let env = &deploy_prod.arguments.as_ref().unwrap()["env"];
+        assert_eq!(env.flags, vec!["--env"]);
+        assert_eq!(
+            env.default,
+            Some(YamlDefaultValue::Value("production".to_string()))
+        );
+
+        // Extended command is not modified.
+        let deploy = defs.get("deploy").unwrap();
+        let env = &deploy.arguments.as_ref().unwrap()["env"];
+        assert_eq!(
+            env.default,
+            Some(YamlDefaultValue::Value("staging".to_string()))
+        );
+    }
+
+    #[test]
+    fn abstract_command() {
+        let yaml_content = r#"
+          base:
+            private: true
+            deprecated: Use 'make' instead.
+            shell: bash
+            env:
+              RUST_LOG: debug
+              PROFILE: dev
+          build:
+            extends: base
+            command: cargo build
+            env:
+              PROFILE: release
+        "#;
+        let defs = load_yaml_defs_from_str(yaml_content).unwrap();
+        assert!(defs.get("base").unwrap().is_abstract());
+
+        let defs = resolve_extends(defs).unwrap();
+        let build = defs.get("build").unwrap();
+        assert!(!build.is_abstract());
+        assert_eq!(build.private, None);
+        assert_eq!(build.deprecated, None);
+        assert_eq!(build.shell.as_deref(), Some("bash"));
+        let env = build.env.as_ref().unwrap();
+        assert_eq!(env["RUST_LOG"], "debug");
+        assert_eq!(env["PROFILE"], "release");
+    }
+
+    #[test]
+    fn unknown_command() {
+        let yaml_content = "run:\n  extends: build\n";
+        let defs = load_yaml_defs_from_str(yaml_content).unwrap();
+
+        let result = resolve_extends(defs);
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Validation { .. })));
+    }
+
+    #[test]
+    fn cycle() {
+        let yaml_content = r#"
+          a:
+            extends: b
+          b:
+            extends: c
+          c:
+            command: echo
+            extends: a
+        "#;
+        let defs = load_yaml_defs_from_str(yaml_content).unwrap();
+
+        let result = resolve_extends(defs);
+        assert!(result.is_err_and(
+            |e| matches!(e, ZxcError::Validation { message, .. } if message == "cyclic 'extends': a -> b -> c -> a")
+        ));
     }
 }
 
 #[cfg(test)]
 mod merge_yaml_defs_tests {
-    use super::merge_yaml_defs;
-    use crate::yaml_command_defs::{YamlCommandDef, YamlCommandDefs};
+    use super::{
+        find_override_warnings, load_yaml_defs_from_str, merge_yaml_defs, resolve_extends,
+    };
+    use crate::yaml_command_defs::{
+        DefinitionOrigin, YamlCommandDef, YamlCommandDefs, YamlDefaultValue,
+    };
+    use std::path::PathBuf;
+
+    /// Create definitions containing single command from specified file.
+    fn defs_from(file_name: &str, command: &str) -> YamlCommandDefs {
+        let def = YamlCommandDef {
+            command: command.to_string(),
+            origin: Some(DefinitionOrigin {
+                path: PathBuf::from(file_name),
+                line: Some(1),
+            }),
+            ..Default::default()
+        };
+        YamlCommandDefs::from([("run".to_string(), def)])
+    }
+
+    /// Mark definitions as coming from overlay definition file.
+    fn overlay(mut defs: YamlCommandDefs) -> YamlCommandDefs {
+        for def in defs.values_mut() {
+            def.overlay = true;
+        }
+        defs
+    }
 
     #[test]
     fn valid_defs() {
@@ -193,7 +1031,7 @@ mod merge_yaml_defs_tests {
             YamlCommandDef {
                 command: "cargo build".to_string(),
                 description: Some("Build the project".to_string()),
-                arguments: None,
+                ..Default::default()
             },
         );
 
@@ -203,7 +1041,7 @@ mod merge_yaml_defs_tests {
             YamlCommandDef {
                 command: "cargo test".to_string(),
                 description: Some("Run tests".to_string()),
-                arguments: None,
+                ..Default::default()
             },
         );
 
@@ -224,7 +1062,7 @@ mod merge_yaml_defs_tests {
             YamlCommandDef {
                 command: "cargo run".to_string(),
                 description: Some("Run the project".to_string()),
-                arguments: None,
+                ..Default::default()
             },
         );
 
@@ -234,7 +1072,7 @@ mod merge_yaml_defs_tests {
             YamlCommandDef {
                 command: "custom run".to_string(),
                 description: Some("Custom run command".to_string()),
-                arguments: None,
+                ..Default::default()
             },
         );
 
@@ -243,6 +1081,145 @@ mod merge_yaml_defs_tests {
         assert_eq!(merged_defs.get("run").unwrap().command, "custom run");
     }
 
+    #[test]
+    fn local_command_replaces_global() {
+        let global_defs = load_yaml_defs_from_str(
+            r#"
+          build:
+            command: echo personal build $SECRET {{ target }}
+            env:
+              SECRET: personal
+            retry:
+              attempts: 3
+            arguments:
+              target:
+                flags: [-t]
+        "#,
+        )
+        .unwrap();
+        let local_defs = load_yaml_defs_from_str(
+            r#"
+          build:
+            command: echo project build $SECRET
+        "#,
+        )
+        .unwrap();
+
+        let merged_defs = merge_yaml_defs(vec![global_defs, local_defs]);
+        let build_def = merged_defs.get("build").unwrap();
+        assert_eq!(build_def.command, "echo project build $SECRET");
+        assert!(build_def.env.is_none());
+        assert!(build_def.retry.is_none());
+        assert!(build_def.arguments.is_none());
+    }
+
+    #[test]
+    fn command_patch() {
+        let base_defs = load_yaml_defs_from_str(
+            r#"
+          run:
+            command: cargo run -- {{ mode }}
+            description: Run the project
+            arguments:
+              mode:
+                flags: [--mode]
+                default: debug
+                description: Build mode.
+          test:
+            command: cargo test
+        "#,
+        )
+        .unwrap();
+        let overlay_defs = load_yaml_defs_from_str(
+            r#"
+          run:
+            arguments:
+              mode:
+                default: release
+              target:
+                flags: [--target]
+                default: x86_64
+          test: null
+        "#,
+        )
+        .unwrap();
+
+        let merged_defs = merge_yaml_defs(vec![base_defs, overlay(overlay_defs)]);
+        assert_eq!(merged_defs.len(), 1);
+        let run_def = merged_defs.get("run").unwrap();
+        assert_eq!(run_def.command, "cargo run -- {{ mode }}");
+        assert_eq!(run_def.description.as_deref(), Some("Run the project"));
+        let arguments = run_def.arguments.as_ref().unwrap();
+        assert_eq!(arguments["mode"].flags, vec!["--mode"]);
+        assert_eq!(
+            arguments["mode"].default,
+            Some(YamlDefaultValue::Value("release".to_string()))
+        );
+        assert_eq!(
+            arguments["mode"].description.as_deref(),
+            Some("Build mode.")
+        );
+        assert_eq!(
+            arguments["target"].default,
+            Some(YamlDefaultValue::Value("x86_64".to_string()))
+        );
+    }
+
+    #[test]
+    fn patched_extended_command() {
+        let base_defs = load_yaml_defs_from_str(
+            r#"
+          deploy:
+            command: ./deploy.sh
+          deploy-prod:
+            extends: deploy
+        "#,
+        )
+        .unwrap();
+        let overlay_defs = load_yaml_defs_from_str(
+            r#"
+          deploy-prod:
+            description: Deploy to production.
+        "#,
+        )
+        .unwrap();
+
+        let merged_defs = merge_yaml_defs(vec![base_defs, overlay(overlay_defs)]);
+        assert_eq!(
+            merged_defs["deploy-prod"].extends.as_deref(),
+            Some("deploy")
+        );
+
+        let defs = resolve_extends(merged_defs).unwrap();
+        let deploy_prod = defs.get("deploy-prod").unwrap();
+        assert_eq!(deploy_prod.command, "./deploy.sh");
+        assert_eq!(
+            deploy_prod.description.as_deref(),
+            Some("Deploy to production.")
+        );
+    }
+
+    #[test]
+    fn shadowed_definitions() {
+        let merged_defs = merge_yaml_defs(vec![
+            defs_from("global.yml", "echo global"),
+            defs_from(".zxc.yml", "echo local"),
+            defs_from("external.yml", "echo external"),
+        ]);
+        let run_def = merged_defs.get("run").unwrap();
+        assert_eq!(run_def.command, "echo external");
+        let shadowed: Vec<String> = run_def.shadowed.iter().map(|x| x.to_string()).collect();
+        assert_eq!(shadowed, vec![".zxc.yml:1", "global.yml:1"]);
+
+        let warnings = find_override_warnings(&merged_defs);
+        assert_eq!(
+            warnings,
+            vec![
+                "command 'run' from 'external.yml:1' overrides definition from '.zxc.yml:1', 'global.yml:1'"
+            ]
+        );
+    }
+
     #[test]
     fn empty_input() {
         let empty_defs = vec![];
@@ -1,6 +1,16 @@
-use std::io::{Error, ErrorKind};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::time::Duration;
 
-use crate::yaml_command_defs::YamlCommandDefs;
+use regex::Regex;
+
+use crate::command_resolver::find_command_variables;
+use crate::error::{SourceSnippet, ZxcError};
+use crate::yaml_command_defs::{
+    DefinitionOrigin, DefinitionScope, YamlArgumentDef, YamlChoices, YamlCommandDef,
+    YamlCommandDefs, YamlDefaultValue, YamlExampleDef, YamlRetryDef, find_definition_snippet,
+    resolve_extends,
+};
 
 /// Flag type.
 #[derive(Clone, Debug)]
@@ -11,6 +21,15 @@ pub enum FlagType {
     Long(String),
 }
 
+impl fmt::Display for FlagType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlagType::Short(x) => write!(f, "-{x}"),
+            FlagType::Long(x) => write!(f, "--{x}"),
+        }
+    }
+}
+
 /// Argument type.
 #[derive(Clone, Debug)]
 pub enum ArgumentType {
@@ -20,19 +39,148 @@ pub enum ArgumentType {
     Positional,
 }
 
-#[derive(Clone, Debug)]
+/// Default value of an argument.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DefaultValue {
+    /// Static value.
+    Static(String),
+    /// Template referencing other arguments. E.g., `{{ name }}-svc`.
+    Template(String),
+    /// Shell command, its output is used as a value. Can reference other arguments.
+    Shell(String),
+}
+
+impl DefaultValue {
+    /// Template evaluated when argument is not provided.
+    /// Static values are handled by CLI parser.
+    pub fn template(&self) -> Option<&str> {
+        match self {
+            DefaultValue::Static(_) => None,
+            DefaultValue::Template(x) | DefaultValue::Shell(x) => Some(x),
+        }
+    }
+}
+
+impl From<YamlDefaultValue> for DefaultValue {
+    fn from(yaml_default_value: YamlDefaultValue) -> Self {
+        match yaml_default_value {
+            YamlDefaultValue::Value(x) if x.contains("{{") || x.contains("{%") => {
+                DefaultValue::Template(x)
+            }
+            YamlDefaultValue::Value(x) => DefaultValue::Static(x),
+            YamlDefaultValue::Shell { sh } => DefaultValue::Shell(sh),
+        }
+    }
+}
+
+/// Allowed values of an argument.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Choices {
+    /// List of values.
+    Values(Vec<String>),
+    /// Shell command, lines of its output are allowed values. Can reference other arguments.
+    Shell {
+        command: String,
+        /// How long output is cached. Not cached if not set.
+        cache: Option<Duration>,
+    },
+}
+
+#[derive(Clone, Debug, Default)]
 pub struct ZxcArgumentDef {
     /// Argument name.
     pub name: String,
-    /// Flags.
+    /// Flags. First short and long flags are primary, others are visible aliases.
     pub flags: Vec<ArgumentType>,
+    /// Flags accepted, but not shown in help.
+    pub hidden_flags: Vec<FlagType>,
     /// Default value.
-    pub default: Option<String>,
+    pub default: Option<DefaultValue>,
     /// Description of an argument.
     pub description: Option<String>,
+    /// Environment variable providing value.
+    pub env: Option<String>,
+    /// Allowed values.
+    pub choices: Option<Choices>,
+    /// Regular expression whole value must match.
+    pub pattern: Option<Regex>,
+    /// Minimum numeric value.
+    pub min: Option<f64>,
+    /// Maximum numeric value.
+    pub max: Option<f64>,
+}
+
+impl ZxcArgumentDef {
+    /// Check if argument has constraints on its value.
+    pub fn is_constrained(&self) -> bool {
+        self.pattern.is_some() || self.min.is_some() || self.max.is_some()
+    }
+
+    /// Check value against constraints of an argument.
+    /// Returns message naming violated constraint.
+    pub fn check_value(&self, value: &str) -> Result<(), String> {
+        if let Some(pattern) = &self.pattern
+            && !pattern.is_match(value)
+        {
+            return Err(format!(
+                "value must match pattern '{}'",
+                pattern_source(pattern)
+            ));
+        }
+
+        if self.min.is_none() && self.max.is_none() {
+            return Ok(());
+        }
+        // NaN and infinity are parsed, but aren't comparable with range bounds.
+        let number: f64 = match value.trim().parse() {
+            Ok(x) if f64::is_finite(x) => x,
+            _ => return Err("value must be a number".to_string()),
+        };
+        if let Some(min) = self.min
+            && number < min
+        {
+            return Err(format!("value must be at least {min}"));
+        }
+        if let Some(max) = self.max
+            && number > max
+        {
+            return Err(format!("value must be at most {max}"));
+        }
+        Ok(())
+    }
+}
+
+/// Pattern as defined by user - without anchors added to match whole value.
+pub fn pattern_source(pattern: &Regex) -> &str {
+    let source = pattern.as_str();
+    source
+        .strip_prefix("^(?:")
+        .and_then(|x| x.strip_suffix(")$"))
+        .unwrap_or(source)
+}
+
+/// Backoff strategy used between retry attempts.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Backoff {
+    /// Same delay between all attempts.
+    Constant,
+    /// Delay is doubled after each attempt.
+    Exponential,
 }
 
 #[derive(Clone, Debug)]
+pub struct ZxcRetryDef {
+    /// Maximum number of attempts, including the first one.
+    pub attempts: u32,
+    /// Delay before the first retry.
+    pub delay: Duration,
+    /// Backoff strategy.
+    pub backoff: Backoff,
+    /// Exit codes for which command is retried. Empty means all non-zero codes.
+    pub on_exit_codes: Vec<i32>,
+}
+
+#[derive(Clone, Debug, Default)]
 pub struct ZxcCommandDef {
     /// Command name.
     pub name: String,
@@ -40,149 +188,584 @@ pub struct ZxcCommandDef {
     pub command: String,
     /// Command description.
     pub description: Option<String>,
+    /// Detailed help.
+    pub help: Option<String>,
+    /// Usage examples.
+    pub examples: Vec<YamlExampleDef>,
+    /// Help section command is listed in.
+    pub group: Option<String>,
+    /// Don't show shell command in help.
+    pub hide_command: bool,
     /// Arguments used by the command.
     pub arguments: Vec<ZxcArgumentDef>,
+    /// Groups of arguments which cannot be used together.
+    pub conflicts: Vec<Vec<String>>,
+    /// Arguments required by an argument, if it's used.
+    pub requires: BTreeMap<String, Vec<String>>,
+    /// Groups of arguments from which exactly one must be used.
+    pub one_of: Vec<Vec<String>>,
+    /// Retry policy.
+    pub retry: Option<ZxcRetryDef>,
+    /// Environment variables set for the command.
+    pub env: BTreeMap<String, String>,
+    /// Shell used to run the command, overrides `shell` setting.
+    pub shell: Option<String>,
+    /// Command is runnable, but not listed in help.
+    pub hidden: bool,
+    /// Command can be run only by other commands.
+    pub private: bool,
+    /// Deprecation message.
+    pub deprecated: Option<String>,
+    /// Scope of definition.
+    pub scope: DefinitionScope,
+    /// Location of definition.
+    pub origin: Option<DefinitionOrigin>,
+    /// Locations of overridden definitions, most recent first.
+    pub shadowed: Vec<DefinitionOrigin>,
+}
+
+impl ZxcCommandDef {
+    /// Check if argument is used in `conflicts`, `requires` or `one_of` relations.
+    pub fn is_argument_related(&self, name: &str) -> bool {
+        self.conflicts
+            .iter()
+            .chain(&self.one_of)
+            .chain(self.requires.values())
+            .any(|x| x.iter().any(|y| y == name))
+            || self.requires.contains_key(name)
+    }
+
+    /// Check if argument must be provided.
+    /// Arguments with default value or choices and arguments used in relations are optional.
+    pub fn is_argument_required(&self, zxc_argument_def: &ZxcArgumentDef) -> bool {
+        zxc_argument_def.default.is_none()
+            && zxc_argument_def.choices.is_none()
+            && !self.is_argument_related(&zxc_argument_def.name)
+    }
 }
 
 pub type ZxcCommandDefs = Vec<ZxcCommandDef>;
 
-/// Change representation from YAML-faced to app-faced.
-pub fn load_zxc_defs(yaml_command_defs: YamlCommandDefs) -> Result<ZxcCommandDefs, Error> {
-    let mut zxc_command_defs = Vec::new();
-    for (yaml_command_name, yaml_command_def) in yaml_command_defs {
-        // Prepare basic parameters.
-        let name = yaml_command_name;
-        let command = yaml_command_def.command;
-        let description = yaml_command_def.description;
-
-        // Iterate through arguments.
-        let mut arguments = Vec::new();
-        if let Some(yaml_arguments) = yaml_command_def.arguments {
-            for (yaml_argument_name, yaml_argument_def) in yaml_arguments {
-                // Prepare basic parameters.
-                let name = yaml_argument_name;
-                let default = yaml_argument_def.default;
-                let description = yaml_argument_def.description;
-
-                // Iterate through flags.
-                let mut flags = Vec::new();
-                for yaml_flag in yaml_argument_def.flags {
-                    let yaml_flag_str = yaml_flag.as_str();
-                    // Handle long option flags.
-                    if yaml_flag_str.starts_with("--") {
-                        let zxc_flag = match yaml_flag_str.strip_prefix("--") {
-                            Some(x) => ArgumentType::Named(FlagType::Long(x.to_string())),
-                            None => {
-                                let error_message =
-                                    format!("Failed to strip '--' prefix from: {yaml_flag_str}");
-                                return Err(Error::new(ErrorKind::InvalidData, error_message));
-                            }
-                        };
-                        flags.push(zxc_flag);
-                    }
-                    // Handle short option flags.
-                    else if yaml_flag_str.starts_with("-") {
-                        if yaml_flag_str.len() != 2 {
-                            let error_message = format!(
-                                "Short flag must consist of two characters: {yaml_flag_str}"
-                            );
-                            return Err(Error::new(ErrorKind::InvalidData, error_message));
-                        }
-
-                        let zxc_flag = match yaml_flag_str.strip_prefix("-") {
-                            Some(x) => ArgumentType::Named(FlagType::Short(x.to_string())),
-                            None => {
-                                let error_message =
-                                    format!("Failed to strip '-' prefix from: {yaml_flag_str}");
-                                return Err(Error::new(ErrorKind::InvalidData, error_message));
-                            }
-                        };
-                        flags.push(zxc_flag);
-                    }
-                    // Handle positional arguments.
-                    else {
-                        let zxc_flag = ArgumentType::Positional;
-                        flags.push(zxc_flag)
-                    }
-                }
+/// Flags reserved by `zxc` for each command.
+pub const RESERVED_FLAGS: [&str; 2] = ["-h", "--help"];
 
-                // Prevent empty flags.
-                if flags.is_empty() {
-                    let error_message = "No flags are defined";
-                    return Err(Error::new(ErrorKind::InvalidData, error_message));
-                }
+/// Argument names reserved by `zxc` for each command.
+pub const RESERVED_ARGUMENT_NAMES: [&str; 1] = ["help"];
 
-                // Prevent more than two flags.
-                if flags.len() > 2 {
-                    let error_message = format!("More than two flags defined: {:#?}", &flags);
-                    return Err(Error::new(ErrorKind::InvalidData, error_message));
-                }
+/// ID of CLI argument group created for relation group with specified index.
+pub fn relation_group_id(rule: &str, index: usize) -> String {
+    format!("{rule}-{}", index + 1)
+}
+
+/// Parse duration string.
+/// Number followed by optional unit - `ms`, `s`, `m` or `h`. Seconds are used if unit is not set.
+pub fn parse_duration(duration_str: &str) -> Option<Duration> {
+    let duration_str = duration_str.trim();
+    let split_index = duration_str
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(duration_str.len());
+    let (value, unit) = duration_str.split_at(split_index);
+    let value: u64 = value.parse().ok()?;
+    match unit {
+        "ms" => Some(Duration::from_millis(value)),
+        "" | "s" => Some(Duration::from_secs(value)),
+        "m" => value.checked_mul(60).map(Duration::from_secs),
+        "h" => value.checked_mul(60 * 60).map(Duration::from_secs),
+        _ => None,
+    }
+}
+
+/// Change retry policy representation from YAML-faced to app-faced.
+fn load_zxc_retry_def(yaml_retry_def: YamlRetryDef) -> Result<ZxcRetryDef, String> {
+    // Prevent retry policy without attempts.
+    if yaml_retry_def.attempts == 0 {
+        return Err("number of retry attempts must be greater than zero".to_string());
+    }
+
+    let delay = match yaml_retry_def.delay {
+        Some(delay_str) => match parse_duration(&delay_str) {
+            Some(x) => x,
+            None => return Err(format!("invalid retry delay: {delay_str}")),
+        },
+        None => Duration::ZERO,
+    };
+
+    let backoff = match yaml_retry_def.backoff.as_deref() {
+        None | Some("constant") => Backoff::Constant,
+        Some("exponential") => Backoff::Exponential,
+        Some(x) => return Err(format!("unknown retry backoff: {x}")),
+    };
+
+    Ok(ZxcRetryDef {
+        attempts: yaml_retry_def.attempts,
+        delay,
+        backoff,
+        on_exit_codes: yaml_retry_def.on_exit_codes.unwrap_or_default(),
+    })
+}
+
+/// Parse flag of an argument.
+fn parse_flag(yaml_flag: &str) -> Result<ArgumentType, String> {
+    // Handle long option flags.
+    if let Some(x) = yaml_flag.strip_prefix("--") {
+        if x.is_empty() || x.starts_with('-') {
+            return Err(format!("invalid long flag: {yaml_flag}"));
+        }
+        Ok(ArgumentType::Named(FlagType::Long(x.to_string())))
+    }
+    // Handle short option flags.
+    else if let Some(x) = yaml_flag.strip_prefix("-") {
+        if yaml_flag.chars().count() != 2 {
+            return Err(format!(
+                "short flag must consist of two characters: {yaml_flag}"
+            ));
+        }
+        Ok(ArgumentType::Named(FlagType::Short(x.to_string())))
+    }
+    // Handle positional arguments.
+    else {
+        Ok(ArgumentType::Positional)
+    }
+}
+
+/// Change argument representation from YAML-faced to app-faced.
+fn load_zxc_argument_def(
+    yaml_argument_name: String,
+    yaml_argument_def: YamlArgumentDef,
+) -> Result<ZxcArgumentDef, String> {
+    // Prepare basic parameters.
+    let name = yaml_argument_name;
+    let default = yaml_argument_def.default.map(DefaultValue::from);
+    let description = yaml_argument_def.description;
+    let env = yaml_argument_def.env;
+    let yaml_flags = yaml_argument_def.flags;
 
-                // Count argument and flag types occurences.
-                let mut num_short = 0;
-                let mut num_long = 0;
-                let mut num_positional = 0;
-                for flag in &flags {
-                    match flag {
-                        ArgumentType::Named(flag_type) => match flag_type {
-                            FlagType::Short(_) => num_short += 1,
-                            FlagType::Long(_) => num_long += 1,
-                        },
-                        ArgumentType::Positional => num_positional += 1,
+    // Prepare allowed values.
+    let choices = match yaml_argument_def.choices {
+        Some(YamlChoices::Values(values)) => {
+            if values.is_empty() {
+                return Err(format!("argument '{name}': no choices are defined"));
+            }
+            Some(Choices::Values(values))
+        }
+        Some(YamlChoices::Shell { sh, cache }) => {
+            let cache = match cache {
+                Some(cache_str) => match parse_duration(&cache_str) {
+                    Some(x) => Some(x),
+                    None => {
+                        return Err(format!(
+                            "argument '{name}': invalid choices cache duration: {cache_str}"
+                        ));
                     }
-                }
+                },
+                None => None,
+            };
+            Some(Choices::Shell { command: sh, cache })
+        }
+        None => None,
+    };
 
-                // Prevent both positional and named argument flags.
-                if num_positional > 0 && (num_short > 0 || num_long > 0) {
-                    let error_message = format!(
-                        "Both positional and named argument flags are defined: {:#?}",
-                        &flags
-                    );
-                    return Err(Error::new(ErrorKind::InvalidData, error_message));
-                }
+    // Prepare value constraints.
+    let pattern = match yaml_argument_def.pattern {
+        Some(pattern_str) => match Regex::new(&format!("^(?:{pattern_str})$")) {
+            Ok(x) => Some(x),
+            Err(e) => return Err(format!("argument '{name}': invalid pattern: {e}")),
+        },
+        None => None,
+    };
+    let min = yaml_argument_def.min;
+    let max = yaml_argument_def.max;
+    if let (Some(min), Some(max)) = (min, max)
+        && min > max
+    {
+        return Err(format!(
+            "argument '{name}': minimum value {min} is greater than maximum value {max}"
+        ));
+    }
+    let is_constrained = pattern.is_some() || min.is_some() || max.is_some();
+    if is_constrained && matches!(choices, Some(Choices::Values(_))) {
+        return Err(format!(
+            "argument '{name}': list of choices cannot be combined with 'pattern', 'min' or 'max'"
+        ));
+    }
 
-                // Prevent multiple positional argument flags.
-                if num_positional > 1 {
-                    let error_message =
-                        format!("Multiple positional arguments are defined: {:#?}", &flags);
-                    return Err(Error::new(ErrorKind::InvalidData, error_message));
-                }
+    // Iterate through flags.
+    let mut flags = Vec::new();
+    for yaml_flag in &yaml_flags {
+        let flag = parse_flag(yaml_flag).map_err(|e| format!("argument '{name}': {e}"))?;
+        flags.push(flag);
+    }
 
-                // Prevent multiple short flags.
-                if num_short > 1 {
-                    let error_message = format!(
-                        "Multiple named argument short flags are defined: {:#?}",
-                        &flags
-                    );
-                    return Err(Error::new(ErrorKind::InvalidData, error_message));
-                }
+    // Prevent empty flags.
+    if flags.is_empty() {
+        return Err(format!("argument '{name}': no flags are defined"));
+    }
 
-                // Prevent multiple long flags.
-                if num_long > 1 {
-                    let error_message = format!(
-                        "Multiple named argument long flags are defined: {:#?}",
-                        &flags
-                    );
-                    return Err(Error::new(ErrorKind::InvalidData, error_message));
+    // Count positional argument flags.
+    let num_positional = flags
+        .iter()
+        .filter(|x| matches!(x, ArgumentType::Positional))
+        .count();
+
+    // Prevent both positional and named argument flags.
+    if num_positional > 0 && num_positional < flags.len() {
+        return Err(format!(
+            "argument '{name}': both positional and named argument flags are defined: {yaml_flags:?}"
+        ));
+    }
+
+    // Prevent multiple positional argument flags.
+    if num_positional > 1 {
+        return Err(format!(
+            "argument '{name}': multiple positional arguments are defined: {yaml_flags:?}"
+        ));
+    }
+
+    // Iterate through hidden flags - only named flags are allowed.
+    let mut hidden_flags = Vec::new();
+    for yaml_flag in &yaml_argument_def.hidden_flags {
+        let flag = parse_flag(yaml_flag).map_err(|e| format!("argument '{name}': {e}"))?;
+        match flag {
+            ArgumentType::Named(x) if num_positional == 0 => hidden_flags.push(x),
+            _ => {
+                return Err(format!(
+                    "argument '{name}': hidden flags are allowed only for named arguments: {yaml_flag}"
+                ));
+            }
+        }
+    }
+
+    Ok(ZxcArgumentDef {
+        name,
+        flags,
+        hidden_flags,
+        default,
+        description,
+        env,
+        choices,
+        pattern,
+        min,
+        max,
+    })
+}
+
+/// Change command representation from YAML-faced to app-faced.
+/// Command template is not analyzed.
+fn convert_zxc_def(
+    yaml_command_name: String,
+    yaml_command_def: YamlCommandDef,
+) -> Result<ZxcCommandDef, ZxcError> {
+    // Point to the offending part of the definition, if known.
+    let definition_origin = yaml_command_def.origin.clone();
+    let validation_error = |message: String, keys: &[&str]| ZxcError::Validation {
+        command: yaml_command_name.clone(),
+        origin: None,
+        message,
+        snippet: definition_origin
+            .as_ref()
+            .and_then(|x| find_definition_snippet(x, keys))
+            .map(Box::new),
+    };
+
+    // Prevent missing command - it can be omitted only by overlays.
+    if yaml_command_def.command.is_empty() {
+        return Err(validation_error("no command is defined".to_string(), &[]));
+    }
+
+    // Prepare basic parameters.
+    let command = yaml_command_def.command;
+    let description = yaml_command_def.description;
+    let help = yaml_command_def.help;
+    let examples = yaml_command_def.examples.unwrap_or_default();
+    let group = yaml_command_def.group;
+    let hide_command = yaml_command_def.hide_command.unwrap_or_default();
+    let env = yaml_command_def.env.unwrap_or_default();
+    let shell = yaml_command_def.shell;
+    let hidden = yaml_command_def.hidden.unwrap_or_default();
+    let private = yaml_command_def.private.unwrap_or_default();
+    let deprecated = yaml_command_def.deprecated;
+    let scope = yaml_command_def.scope;
+    let origin = yaml_command_def.origin;
+    let shadowed = yaml_command_def.shadowed;
+    let retry = match yaml_command_def.retry {
+        Some(x) => Some(load_zxc_retry_def(x).map_err(|e| validation_error(e, &["retry"]))?),
+        None => None,
+    };
+
+    // Iterate through arguments.
+    let mut arguments = Vec::new();
+    if let Some(yaml_arguments) = yaml_command_def.arguments {
+        for (yaml_argument_name, yaml_argument_def) in yaml_arguments {
+            let keys = ["arguments", yaml_argument_name.as_str()];
+            let argument = load_zxc_argument_def(yaml_argument_name.clone(), yaml_argument_def)
+                .map_err(|e| validation_error(e, &keys))?;
+            arguments.push(argument);
+        }
+    }
+
+    // Prevent reserved flags and flags used multiple times.
+    let mut flag_owners: BTreeMap<String, &str> = BTreeMap::new();
+    for argument in &arguments {
+        let named_flags = argument.flags.iter().filter_map(|x| match x {
+            ArgumentType::Named(flag_type) => Some(flag_type),
+            ArgumentType::Positional => None,
+        });
+        for flag in named_flags.chain(&argument.hidden_flags) {
+            let flag = flag.to_string();
+            if RESERVED_FLAGS.contains(&flag.as_str()) {
+                return Err(validation_error(
+                    format!("flag '{flag}' of argument '{}' is reserved", argument.name),
+                    &["arguments", &argument.name],
+                ));
+            }
+            match flag_owners.insert(flag.clone(), &argument.name) {
+                Some(x) if x == argument.name => {
+                    return Err(validation_error(
+                        format!("flag '{flag}' is defined multiple times by argument '{x}'"),
+                        &["arguments", x],
+                    ));
                 }
+                Some(x) => {
+                    return Err(validation_error(
+                        format!(
+                            "flag '{flag}' is used by both argument '{x}' and '{}'",
+                            argument.name
+                        ),
+                        &["arguments", &argument.name],
+                    ));
+                }
+                None => (),
+            }
+        }
+    }
 
-                arguments.push(ZxcArgumentDef {
-                    name,
-                    flags,
-                    default,
-                    description,
-                });
+    // Check argument relations.
+    let conflicts = yaml_command_def.conflicts.unwrap_or_default();
+    let requires = yaml_command_def.requires.unwrap_or_default();
+    let one_of = yaml_command_def.one_of.unwrap_or_default();
+    let is_defined = |x: &String| arguments.iter().any(|y| &y.name == x);
+    for (rule, groups) in [("conflicts", &conflicts), ("one_of", &one_of)] {
+        for group in groups {
+            if group.len() < 2 {
+                return Err(validation_error(
+                    format!("'{rule}' group must contain at least two arguments: {group:?}"),
+                    &[rule],
+                ));
             }
+            if let Some(x) = group.iter().find(|x| !is_defined(x)) {
+                return Err(validation_error(
+                    format!("'{rule}' references undefined argument '{x}'"),
+                    &[rule],
+                ));
+            }
+        }
+    }
+    for (name, required) in &requires {
+        if let Some(x) = std::iter::once(name)
+            .chain(required)
+            .find(|x| !is_defined(x))
+        {
+            return Err(validation_error(
+                format!("'requires' references undefined argument '{x}'"),
+                &["requires"],
+            ));
         }
+    }
 
-        zxc_command_defs.push(ZxcCommandDef {
-            name,
-            command,
-            description,
-            arguments,
+    // Prevent argument names used by CLI parser.
+    let group_ids: Vec<String> = [("conflicts", &conflicts), ("one_of", &one_of)]
+        .into_iter()
+        .flat_map(|(rule, groups)| (0..groups.len()).map(move |x| relation_group_id(rule, x)))
+        .collect();
+    if let Some(x) = arguments
+        .iter()
+        .find(|x| RESERVED_ARGUMENT_NAMES.contains(&x.name.as_str()) || group_ids.contains(&x.name))
+    {
+        return Err(validation_error(
+            format!("argument name '{}' is reserved", x.name),
+            &["arguments", &x.name],
+        ));
+    }
+
+    let zxc_command_def = ZxcCommandDef {
+        name: yaml_command_name.clone(),
+        command,
+        description,
+        help,
+        examples,
+        group,
+        hide_command,
+        arguments,
+        conflicts,
+        requires,
+        one_of,
+        retry,
+        env,
+        shell,
+        hidden,
+        private,
+        deprecated,
+        scope,
+        origin,
+        shadowed,
+    };
+
+    // Prevent required positional arguments after optional ones.
+    // Positional arguments are ordered by name.
+    let mut optional_positional: Option<&str> = None;
+    for argument in &zxc_command_def.arguments {
+        if !matches!(argument.flags.first(), Some(ArgumentType::Positional)) {
+            continue;
+        }
+        let is_required = zxc_command_def.is_argument_required(argument);
+        match optional_positional {
+            Some(x) if is_required => {
+                return Err(validation_error(
+                    format!(
+                        "required positional argument '{}' cannot follow optional positional argument '{x}'",
+                        argument.name
+                    ),
+                    &["arguments", &argument.name],
+                ));
+            }
+            None if !is_required => optional_positional = Some(&argument.name),
+            _ => (),
+        }
+    }
+
+    Ok(zxc_command_def)
+}
+
+/// Templates of a command - command itself, dynamic default values and choices of arguments.
+fn command_templates(zxc_command_def: &ZxcCommandDef) -> Vec<&str> {
+    let default_templates = zxc_command_def
+        .arguments
+        .iter()
+        .filter_map(|x| x.default.as_ref().and_then(DefaultValue::template));
+    let choices_templates = zxc_command_def
+        .arguments
+        .iter()
+        .filter_map(|x| match &x.choices {
+            Some(Choices::Shell { command, .. }) => Some(command.as_str()),
+            _ => None,
         });
+    std::iter::once(zxc_command_def.command.as_str())
+        .chain(default_templates)
+        .chain(choices_templates)
+        .collect()
+}
+
+/// Find byte offset of an identifier in a template, ignoring its occurrences in other words.
+fn find_identifier(template: &str, identifier: &str) -> Option<usize> {
+    let is_word_char = |x: char| x.is_alphanumeric() || x == '_';
+    template
+        .match_indices(identifier)
+        .map(|(x, _)| x)
+        .find(|&x| {
+            let before = template[..x].chars().next_back();
+            let after = template[x + identifier.len()..].chars().next();
+            !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)
+        })
+}
+
+/// Find problems with command templates.
+/// Both syntax errors and references to undefined arguments are reported.
+fn find_template_errors(zxc_command_def: &ZxcCommandDef) -> Vec<ZxcError> {
+    let mut errors = Vec::new();
+    for template in command_templates(zxc_command_def) {
+        let variables = match find_command_variables(template) {
+            Ok(x) => x,
+            Err(e) => {
+                errors.push(
+                    ZxcError::from_template_error(&zxc_command_def.name, template, &e)
+                        .with_origin(zxc_command_def.origin.as_ref()),
+                );
+                continue;
+            }
+        };
+
+        for variable in variables {
+            if zxc_command_def.arguments.iter().any(|x| x.name == variable) {
+                continue;
+            }
+
+            let snippet = match find_identifier(template, &variable) {
+                Some(offset) => SourceSnippet::from_offset(None, template, offset, variable.len()),
+                None => None,
+            };
+            let error = ZxcError::Template {
+                command: zxc_command_def.name.clone(),
+                origin: None,
+                message: format!("reference to undefined argument '{variable}'"),
+                snippet: snippet.map(Box::new),
+            };
+            errors.push(error.with_origin(zxc_command_def.origin.as_ref()));
+        }
     }
+    errors
+}
 
+/// Find arguments not referenced by command templates.
+/// Returns warning messages.
+pub fn find_template_warnings(zxc_command_def: &ZxcCommandDef) -> Vec<String> {
+    // Template errors are reported separately.
+    let mut variables = BTreeSet::new();
+    for template in command_templates(zxc_command_def) {
+        match find_command_variables(template) {
+            Ok(x) => variables.extend(x),
+            Err(_) => return vec![],
+        }
+    }
+
+    zxc_command_def
+        .arguments
+        .iter()
+        .filter(|x| !variables.contains(&x.name))
+        .map(|x| {
+            format!(
+                "argument '{}' of command '{}' is not used by the command",
+                x.name, zxc_command_def.name
+            )
+        })
+        .collect()
+}
+
+/// Change command representation from YAML-faced to app-faced.
+/// Command template is analyzed against defined arguments.
+/// Returns all problems found.
+pub fn load_zxc_def(
+    yaml_command_name: String,
+    yaml_command_def: YamlCommandDef,
+) -> Result<ZxcCommandDef, Vec<ZxcError>> {
+    let origin = yaml_command_def.origin.clone();
+    let zxc_command_def = match convert_zxc_def(yaml_command_name, yaml_command_def) {
+        Ok(x) => x,
+        Err(e) => return Err(vec![e.with_origin(origin.as_ref())]),
+    };
+
+    let template_errors = find_template_errors(&zxc_command_def);
+    if !template_errors.is_empty() {
+        return Err(template_errors);
+    }
+
+    Ok(zxc_command_def)
+}
+
+/// Change representation from YAML-faced to app-faced.
+/// `extends` are resolved, abstract commands are skipped.
+/// Only first problem found is returned.
+pub fn load_zxc_defs(yaml_command_defs: YamlCommandDefs) -> Result<ZxcCommandDefs, ZxcError> {
+    let yaml_command_defs = resolve_extends(yaml_command_defs)?;
+    let mut zxc_command_defs = Vec::new();
+    for (yaml_command_name, yaml_command_def) in yaml_command_defs {
+        if yaml_command_def.is_abstract() {
+            continue;
+        }
+        match load_zxc_def(yaml_command_name, yaml_command_def) {
+            Ok(x) => zxc_command_defs.push(x),
+            Err(mut errors) => return Err(errors.remove(0)),
+        }
+    }
     Ok(zxc_command_defs)
 }
 
@@ -195,3 +778,704 @@ pub fn find_zxc_command_def(
         .into_iter()
         .find(|x| x.name == command_name)
 }
+
+#[cfg(test)]
+mod find_template_errors_tests {
+    use super::{ZxcArgumentDef, ZxcCommandDef, find_template_errors};
+    use crate::error::ZxcError;
+
+    fn command_def(command: &str, argument_names: &[&str]) -> ZxcCommandDef {
+        let arguments = argument_names
+            .iter()
+            .map(|x| ZxcArgumentDef {
+                name: x.to_string(),
+                ..Default::default()
+            })
+            .collect();
+        ZxcCommandDef {
+            name: "greet".to_string(),
+            command: command.to_string(),
+            description: None,
+            arguments,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn valid() {
+        let def = command_def("echo {{ name }}", &["name"]);
+        assert!(find_template_errors(&def).is_empty());
+    }
+
+    #[test]
+    fn undefined_arguments() {
+        let def = command_def("echo {{ nmae }} {{ greeting }}", &["name"]);
+        let errors = find_template_errors(&def);
+        assert_eq!(errors.len(), 2);
+        assert!(
+            errors
+                .iter()
+                .all(|e| matches!(e, ZxcError::Template { .. }))
+        );
+        assert!(errors[1].to_string().contains("'nmae'"));
+    }
+
+    #[test]
+    fn undefined_argument_in_another_word() {
+        let def = command_def("echo --profile {{ profil }}", &[]);
+        let errors = find_template_errors(&def);
+        match &errors[0] {
+            ZxcError::Template {
+                snippet: Some(snippet),
+                ..
+            } => assert_eq!(snippet.column, 19),
+            e => panic!("Unexpected error: {e:?}"),
+        }
+    }
+
+    #[test]
+    fn syntax_error() {
+        let def = command_def("echo {{ name", &["name"]);
+        let errors = find_template_errors(&def);
+        assert_eq!(errors.len(), 1);
+    }
+}
+
+#[cfg(test)]
+mod find_template_warnings_tests {
+    use super::{ZxcArgumentDef, ZxcCommandDef, find_template_warnings};
+
+    #[test]
+    fn unused_argument() {
+        let arguments = ["name", "greeting"]
+            .iter()
+            .map(|x| ZxcArgumentDef {
+                name: x.to_string(),
+                ..Default::default()
+            })
+            .collect();
+        let def = ZxcCommandDef {
+            name: "greet".to_string(),
+            command: "echo {{ name }}".to_string(),
+            description: None,
+            arguments,
+            ..Default::default()
+        };
+
+        let warnings = find_template_warnings(&def);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("'greeting'"));
+    }
+}
+
+#[cfg(test)]
+mod load_zxc_defs_tests {
+    use super::{Choices, DefaultValue, find_template_warnings, load_zxc_defs};
+    use crate::error::ZxcError;
+    use crate::yaml_command_defs::{load_yaml_defs_from_str, set_definition_origins};
+    use std::path::Path;
+    use std::time::Duration;
+
+    #[test]
+    fn valid() {
+        let yaml_content = r#"
+          greet:
+            command: echo "Hello {{ name }}!"
+            arguments:
+              name:
+                flags: ["-n", "--name"]
+                default: User
+        "#;
+        let yaml_command_defs = load_yaml_defs_from_str(yaml_content).unwrap();
+
+        let result = load_zxc_defs(yaml_command_defs);
+        assert!(result.is_ok_and(|x| x.len() == 1));
+    }
+
+    #[test]
+    fn undefined_argument() {
+        let yaml_content = r#"
+          greet:
+            command: echo "Hello {{ nmae }}!"
+            arguments:
+              name:
+                flags: ["-n", "--name"]
+                default: User
+        "#;
+        let yaml_command_defs = load_yaml_defs_from_str(yaml_content).unwrap();
+
+        let result = load_zxc_defs(yaml_command_defs);
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Template { .. })));
+    }
+
+    #[test]
+    fn invalid_argument() {
+        let yaml_content = r#"
+          greet:
+            command: echo "Hello {{ name }}!"
+            arguments:
+              name:
+                flags: ["-n", "name"]
+        "#;
+        let yaml_command_defs = load_yaml_defs_from_str(yaml_content).unwrap();
+
+        let result = load_zxc_defs(yaml_command_defs);
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Validation { .. })));
+    }
+
+    #[test]
+    fn error_origin() {
+        let yaml_content = r#"
+          greet:
+            command: echo "Hello {{ nmae }}!"
+          build:
+            command: cargo build
+            arguments:
+              mode:
+                flags: ["-m", "mode"]
+        "#;
+        for name in ["greet", "build"] {
+            let mut yaml_command_defs = load_yaml_defs_from_str(yaml_content).unwrap();
+            yaml_command_defs.retain(|x, _| x == name);
+            set_definition_origins(&mut yaml_command_defs, Path::new(".zxc.yml"), yaml_content);
+
+            let result = load_zxc_defs(yaml_command_defs);
+            assert!(
+                result.is_err_and(|e| matches!(
+                    &e,
+                    ZxcError::Validation { origin: Some(x), .. } | ZxcError::Template { origin: Some(x), .. }
+                        if x.path == Path::new(".zxc.yml") && x.line.is_some()
+                )),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn flag_aliases() {
+        let yaml_content = r#"
+          deploy:
+            command: ./deploy.sh {{ env }}
+            arguments:
+              env:
+                flags: [--env, --environment, -e, -E]
+                hidden_flags: [--stage]
+        "#;
+        let yaml_command_defs = load_yaml_defs_from_str(yaml_content).unwrap();
+
+        let zxc_command_defs = load_zxc_defs(yaml_command_defs).unwrap();
+        let argument = &zxc_command_defs[0].arguments[0];
+        assert_eq!(argument.flags.len(), 4);
+        assert_eq!(argument.hidden_flags[0].to_string(), "--stage");
+    }
+
+    #[test]
+    fn flag_collisions() {
+        let cases = [
+            (
+                "[-e]",
+                "[]",
+                "flag '-e' is used by both argument 'env' and 'region'",
+            ),
+            (
+                "[-r]",
+                "[--env]",
+                "flag '--env' is used by both argument 'env' and 'region'",
+            ),
+            (
+                "[-r, --region, -r]",
+                "[]",
+                "flag '-r' is defined multiple times by argument 'region'",
+            ),
+        ];
+        for (flags, hidden_flags, message) in cases {
+            let yaml_content = format!(
+                r#"
+          deploy:
+            command: ./deploy.sh {{{{ env }}}} {{{{ region }}}}
+            arguments:
+              env:
+                flags: [--env, -e]
+              region:
+                flags: {flags}
+                hidden_flags: {hidden_flags}
+        "#
+            );
+            let yaml_command_defs = load_yaml_defs_from_str(&yaml_content).unwrap();
+
+            let result = load_zxc_defs(yaml_command_defs);
+            assert!(
+                result
+                    .is_err_and(|e| matches!(e, ZxcError::Validation { .. })
+                        && e.to_string().contains(message)),
+                "{message}"
+            );
+        }
+    }
+
+    #[test]
+    fn reserved_flags() {
+        for flag in ["-h", "--help"] {
+            let yaml_content = format!(
+                r#"
+          greet:
+            command: echo "Hello {{{{ name }}}}!"
+            arguments:
+              name:
+                flags: [--name]
+                hidden_flags: [{flag}]
+        "#
+            );
+            let yaml_command_defs = load_yaml_defs_from_str(&yaml_content).unwrap();
+
+            let result = load_zxc_defs(yaml_command_defs);
+            assert!(
+                result.is_err_and(|e| e.to_string().contains("is reserved")),
+                "{flag}"
+            );
+        }
+    }
+
+    #[test]
+    fn reserved_argument_names() {
+        for name in ["help", "conflicts-1", "one_of-2"] {
+            let yaml_content = format!(
+                r#"
+          deploy:
+            command: ./deploy.sh
+            conflicts: [[a, b]]
+            one_of: [[a, c], [b, c]]
+            arguments:
+              a:
+                flags: [-a]
+              b:
+                flags: [-b]
+              c:
+                flags: [-c]
+              {name}:
+                flags: [--x]
+        "#
+            );
+            let yaml_command_defs = load_yaml_defs_from_str(&yaml_content).unwrap();
+
+            let result = load_zxc_defs(yaml_command_defs);
+            assert!(
+                result.is_err_and(|e| e
+                    .to_string()
+                    .contains(&format!("argument name '{name}' is reserved"))),
+                "{name}"
+            );
+        }
+
+        // Group IDs are reserved only if such groups are defined.
+        let yaml_content = r#"
+          deploy:
+            command: ./deploy.sh
+            arguments:
+              one_of-1:
+                flags: [--x]
+        "#;
+        let yaml_command_defs = load_yaml_defs_from_str(yaml_content).unwrap();
+        assert!(load_zxc_defs(yaml_command_defs).is_ok());
+    }
+
+    #[test]
+    fn invalid_long_flag() {
+        let yaml_content = r#"
+          greet:
+            command: echo "Hello {{ name }}!"
+            arguments:
+              name:
+                flags: ["--"]
+        "#;
+        let yaml_command_defs = load_yaml_defs_from_str(yaml_content).unwrap();
+
+        let result = load_zxc_defs(yaml_command_defs);
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Validation { .. })));
+    }
+
+    #[test]
+    fn positional_order() {
+        // Positional arguments are ordered by name.
+        let yaml_content = r#"
+          copy:
+            command: cp {{ a_source }} {{ b_target }}
+            arguments:
+              a_source:
+                flags: [source]
+              b_target:
+                flags: [target]
+                default: .
+        "#;
+        let yaml_command_defs = load_yaml_defs_from_str(yaml_content).unwrap();
+        assert!(load_zxc_defs(yaml_command_defs).is_ok());
+
+        let yaml_content = r#"
+          copy:
+            command: cp {{ a_source }} {{ b_target }}
+            arguments:
+              a_source:
+                flags: [source]
+                default: .
+              b_target:
+                flags: [target]
+        "#;
+        let yaml_command_defs = load_yaml_defs_from_str(yaml_content).unwrap();
+        let result = load_zxc_defs(yaml_command_defs);
+        assert!(result.is_err_and(|e| {
+            matches!(e, ZxcError::Validation { .. })
+                && e.to_string()
+                    .contains("'b_target' cannot follow optional positional argument 'a_source'")
+        }));
+    }
+
+    #[test]
+    fn hidden_positional_flag() {
+        let yaml_content = r#"
+          greet:
+            command: echo "Hello {{ name }}!"
+            arguments:
+              name:
+                flags: [name]
+                hidden_flags: [--name]
+        "#;
+        let yaml_command_defs = load_yaml_defs_from_str(yaml_content).unwrap();
+
+        let result = load_zxc_defs(yaml_command_defs);
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Validation { .. })));
+    }
+
+    #[test]
+    fn abstract_command() {
+        let yaml_content = r#"
+          base:
+            private: true
+            env:
+              RUST_LOG: debug
+          build:
+            extends: base
+            command: cargo build
+        "#;
+        let yaml_command_defs = load_yaml_defs_from_str(yaml_content).unwrap();
+
+        let zxc_command_defs = load_zxc_defs(yaml_command_defs).unwrap();
+        assert_eq!(zxc_command_defs.len(), 1);
+        assert_eq!(zxc_command_defs[0].name, "build");
+        assert_eq!(zxc_command_defs[0].env["RUST_LOG"], "debug");
+    }
+
+    #[test]
+    fn missing_command() {
+        let yaml_content = r#"
+          name:
+            description: Some description.
+        "#;
+        let yaml_command_defs = load_yaml_defs_from_str(yaml_content).unwrap();
+
+        let result = load_zxc_defs(yaml_command_defs);
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Validation { .. })));
+    }
+
+    #[test]
+    fn dynamic_defaults() {
+        let yaml_content = r#"
+          deploy:
+            command: ./deploy.sh {{ service }} {{ tag }} {{ mode }}
+            arguments:
+              name:
+                flags: [name]
+              service:
+                flags: [--service]
+                default: "{{ name }}-svc"
+              tag:
+                flags: [--tag]
+                default: { sh: git rev-parse --short HEAD }
+              mode:
+                flags: [--mode]
+                default: fast
+        "#;
+        let yaml_command_defs = load_yaml_defs_from_str(yaml_content).unwrap();
+
+        let zxc_command_defs = load_zxc_defs(yaml_command_defs).unwrap();
+        let defaults: Vec<_> = zxc_command_defs[0]
+            .arguments
+            .iter()
+            .map(|x| x.default.clone())
+            .collect();
+        assert_eq!(
+            defaults,
+            vec![
+                Some(DefaultValue::Static("fast".to_string())),
+                None,
+                Some(DefaultValue::Template("{{ name }}-svc".to_string())),
+                Some(DefaultValue::Shell(
+                    "git rev-parse --short HEAD".to_string()
+                )),
+            ]
+        );
+        // Argument referenced only by default value is used.
+        assert!(find_template_warnings(&zxc_command_defs[0]).is_empty());
+    }
+
+    #[test]
+    fn choices() {
+        let yaml_content = r#"
+          logs:
+            command: docker compose logs {{ service }} --tail {{ lines }}
+            arguments:
+              service:
+                flags: [service]
+                choices: { sh: docker compose config --services, cache: 10m }
+              lines:
+                flags: [--lines]
+                choices: ["10", "100"]
+                default: "10"
+        "#;
+        let yaml_command_defs = load_yaml_defs_from_str(yaml_content).unwrap();
+
+        let zxc_command_defs = load_zxc_defs(yaml_command_defs).unwrap();
+        let arguments = &zxc_command_defs[0].arguments;
+        assert_eq!(
+            arguments[0].choices,
+            Some(Choices::Values(vec!["10".to_string(), "100".to_string()]))
+        );
+        assert_eq!(
+            arguments[1].choices,
+            Some(Choices::Shell {
+                command: "docker compose config --services".to_string(),
+                cache: Some(Duration::from_secs(600)),
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_choices() {
+        let yaml_content = r#"
+          logs:
+            command: docker compose logs {{ service }}
+            arguments:
+              service:
+                flags: [service]
+                choices: { sh: docker compose config --services, cache: soon }
+        "#;
+        let yaml_command_defs = load_yaml_defs_from_str(yaml_content).unwrap();
+
+        let result = load_zxc_defs(yaml_command_defs);
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Validation { .. })));
+    }
+
+    #[test]
+    fn constraints() {
+        let yaml_content = r#"
+          serve:
+            command: ./serve.sh {{ port }} {{ tag }}
+            arguments:
+              port:
+                flags: [--port]
+                min: 1
+                max: 65535
+              tag:
+                flags: [--tag]
+                pattern: 'v\d+'
+        "#;
+        let yaml_command_defs = load_yaml_defs_from_str(yaml_content).unwrap();
+
+        let zxc_command_defs = load_zxc_defs(yaml_command_defs).unwrap();
+        let arguments = &zxc_command_defs[0].arguments;
+        assert_eq!(
+            (arguments[0].min, arguments[0].max),
+            (Some(1.0), Some(65535.0))
+        );
+        assert!(arguments[1].pattern.as_ref().unwrap().is_match("v12"));
+    }
+
+    #[test]
+    fn invalid_constraints() {
+        let cases = [
+            "pattern: 'v(\\d+'",
+            "min: 10\n                max: 1",
+            "choices: [a, b]\n                pattern: '[ab]'",
+        ];
+        for case in cases {
+            let yaml_content = format!(
+                r#"
+          serve:
+            command: ./serve.sh {{{{ port }}}}
+            arguments:
+              port:
+                flags: [--port]
+                {case}
+        "#
+            );
+            let yaml_command_defs = load_yaml_defs_from_str(&yaml_content).unwrap();
+
+            let result = load_zxc_defs(yaml_command_defs);
+            assert!(
+                result.is_err_and(|e| matches!(e, ZxcError::Validation { .. })),
+                "{case}"
+            );
+        }
+    }
+
+    #[test]
+    fn relations() {
+        let yaml_content = r#"
+          fetch:
+            command: ./fetch.sh {{ file | default('') }} {{ url | default('') }} {{ token | default('') }}
+            arguments:
+              file:
+                flags: [--file]
+              url:
+                flags: [--url]
+              token:
+                flags: [--token]
+            one_of:
+              - [file, url]
+            requires:
+              url: [token]
+        "#;
+        let yaml_command_defs = load_yaml_defs_from_str(yaml_content).unwrap();
+
+        let zxc_command_defs = load_zxc_defs(yaml_command_defs).unwrap();
+        assert_eq!(zxc_command_defs[0].one_of, vec![vec!["file", "url"]]);
+        assert_eq!(zxc_command_defs[0].requires["url"], vec!["token"]);
+    }
+
+    #[test]
+    fn invalid_relations() {
+        let cases = [
+            "conflicts: [[file]]",
+            "one_of: [[file, uri]]",
+            "requires: { url: [tokne] }",
+        ];
+        for case in cases {
+            let yaml_content = format!(
+                r#"
+          fetch:
+            command: ./fetch.sh {{{{ file }}}} {{{{ url }}}}
+            arguments:
+              file:
+                flags: [--file]
+              url:
+                flags: [--url]
+            {case}
+        "#
+            );
+            let yaml_command_defs = load_yaml_defs_from_str(&yaml_content).unwrap();
+
+            let result = load_zxc_defs(yaml_command_defs);
+            assert!(
+                result.is_err_and(|e| matches!(e, ZxcError::Validation { .. })),
+                "{case}"
+            );
+        }
+    }
+
+    #[test]
+    fn undefined_argument_in_default() {
+        let yaml_content = r#"
+          deploy:
+            command: ./deploy.sh {{ tag }}
+            arguments:
+              tag:
+                flags: [--tag]
+                default: { sh: "git describe {{ revison }}" }
+        "#;
+        let yaml_command_defs = load_yaml_defs_from_str(yaml_content).unwrap();
+
+        let result = load_zxc_defs(yaml_command_defs);
+        assert!(result.is_err_and(|e| matches!(e, ZxcError::Template { .. })));
+    }
+}
+
+#[cfg(test)]
+mod check_value_tests {
+    use super::{ArgumentType, ZxcArgumentDef};
+    use regex::Regex;
+
+    fn argument_def(pattern: Option<&str>, min: Option<f64>, max: Option<f64>) -> ZxcArgumentDef {
+        ZxcArgumentDef {
+            name: "value".to_string(),
+            flags: vec![ArgumentType::Positional],
+            pattern: pattern.map(|x| Regex::new(&format!("^(?:{x})$")).unwrap()),
+            min,
+            max,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn pattern() {
+        let def = argument_def(Some("v\\d+"), None, None);
+        assert!(def.check_value("v1").is_ok());
+        // Whole value must match.
+        assert_eq!(
+            def.check_value("v1-rc").unwrap_err(),
+            "value must match pattern 'v\\d+'"
+        );
+    }
+
+    #[test]
+    fn range() {
+        let def = argument_def(None, Some(1.0), Some(10.0));
+        assert!(def.check_value("1").is_ok());
+        assert!(def.check_value("2.5").is_ok());
+        assert_eq!(
+            def.check_value("0").unwrap_err(),
+            "value must be at least 1"
+        );
+        assert_eq!(
+            def.check_value("11").unwrap_err(),
+            "value must be at most 10"
+        );
+        assert_eq!(
+            def.check_value("ten").unwrap_err(),
+            "value must be a number"
+        );
+    }
+
+    #[test]
+    fn not_finite() {
+        let def = argument_def(None, Some(1.0), Some(10.0));
+        for value in ["NaN", "nan", "inf", "-infinity"] {
+            assert_eq!(
+                def.check_value(value).unwrap_err(),
+                "value must be a number"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod parse_duration_tests {
+    use super::parse_duration;
+    use std::time::Duration;
+
+    #[test]
+    fn units() {
+        assert_eq!(parse_duration("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_duration("2s"), Some(Duration::from_secs(2)));
+        assert_eq!(parse_duration("3m"), Some(Duration::from_secs(180)));
+        assert_eq!(parse_duration("1h"), Some(Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn no_unit() {
+        assert_eq!(parse_duration("5"), Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("s"), None);
+        assert_eq!(parse_duration("2 days"), None);
+        assert_eq!(parse_duration("-1s"), None);
+    }
+
+    #[test]
+    fn overflow() {
+        assert_eq!(parse_duration("99999999999999999999s"), None);
+        assert_eq!(parse_duration("9999999999999999999m"), None);
+        assert_eq!(parse_duration("9999999999999999h"), None);
+    }
+}